@@ -1,15 +1,69 @@
+use rustybuzz::ttf_parser::GlyphId;
+
 use crate::color::Color;
+use crate::color_glyph;
 use crate::flatten::{flatten, stroke};
 use crate::geom::{Affine, Point};
-use crate::path::Path;
-use crate::raster::{Rasterizer, Segment};
-use crate::text::{Font, Glyph, TextLayout};
+use crate::glyph_cache::{quantize, GlyphCache, GlyphKey, MAX_MASK_SIZE};
+use crate::image::Image;
+use crate::paint::{BlendMode, Extend, Paint, Pattern};
+use crate::paragraph::ParagraphStyle;
+use crate::path::{FillRule, Path, StrokeStyle};
+use crate::raster::{blit_mask, Composite, CompositeLayer, Mask, Rasterizer, Segment};
+use crate::shader::Shader;
+use crate::text::{
+    AttributedText, Font, FontMetrics, Glyph, TextDecoration, TextLayout, TextStyle,
+};
 
 const MAX_SEGMENTS: usize = 256;
 
+/// A rectangle of pixels, with exclusive maximum coordinates.
+#[derive(Copy, Clone)]
+struct Bounds {
+    min_x: usize,
+    min_y: usize,
+    max_x: usize,
+    max_y: usize,
+}
+
+impl Bounds {
+    #[inline]
+    fn empty() -> Bounds {
+        Bounds {
+            min_x: 0,
+            min_y: 0,
+            max_x: 0,
+            max_y: 0,
+        }
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.max_x <= self.min_x || self.max_y <= self.min_y
+    }
+
+    #[inline]
+    fn intersect(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min_x: self.min_x.max(other.min_x),
+            min_y: self.min_y.max(other.min_y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y),
+        }
+    }
+}
+
+struct ClipMask {
+    data: Vec<f32>,
+    bounds: Bounds,
+}
+
 pub struct Renderer {
     segments: Vec<Segment>,
     rasterizer: Rasterizer,
+    clip_masks: Vec<ClipMask>,
+    layers: Vec<Vec<u32>>,
+    glyph_cache: GlyphCache,
 }
 
 impl Renderer {
@@ -17,9 +71,21 @@ impl Renderer {
         Renderer {
             segments: Vec::with_capacity(MAX_SEGMENTS),
             rasterizer: Rasterizer::new(),
+            clip_masks: Vec::new(),
+            layers: Vec::new(),
+            glyph_cache: GlyphCache::new(),
         }
     }
 
+    /// Discards all cached glyph outlines and rasterized glyphs.
+    ///
+    /// Glyphs are cached per font, size and subpixel offset as they are drawn. Each part of the
+    /// cache is cleared once it holds a fixed number of entries, but clearing it releases the
+    /// memory held for fonts which are no longer in use sooner.
+    pub fn clear_glyph_cache(&mut self) {
+        self.glyph_cache.clear();
+    }
+
     pub fn canvas<'a>(
         &'a mut self,
         data: &'a mut [u32],
@@ -34,6 +100,10 @@ impl Renderer {
             width,
             height,
             transform: Affine::id(),
+            blend_mode: BlendMode::SrcOver,
+            clip: None,
+            clip_top: 0,
+            layer_top: 0,
         }
     }
 }
@@ -50,6 +120,13 @@ pub struct Canvas<'a> {
     width: usize,
     height: usize,
     transform: Affine,
+    blend_mode: BlendMode,
+    // Index of the active clip mask in `Renderer::clip_masks`, if any.
+    clip: Option<usize>,
+    // Index of the first clip mask not in use by this canvas or its ancestors.
+    clip_top: usize,
+    // Index of the first layer buffer not in use by this canvas or its ancestors.
+    layer_top: usize,
 }
 
 impl<'a> Canvas<'a> {
@@ -75,7 +152,183 @@ impl<'a> Canvas<'a> {
         result
     }
 
+    /// Sets the blend mode used by all drawing performed inside `f`.
+    ///
+    /// Like the transform and the clip, the blend mode is part of the canvas state rather than a
+    /// parameter of each drawing method, so that it applies uniformly to paths, images and text
+    /// and is always restored afterwards. To draw a single path with its own blend mode, call
+    /// [`fill_path`](Canvas::fill_path) or [`stroke_path`](Canvas::stroke_path) from `f`.
+    pub fn with_blend_mode<F, R>(&mut self, blend_mode: BlendMode, f: F) -> R
+    where
+        F: FnOnce(&mut Canvas) -> R,
+    {
+        let saved = self.blend_mode;
+        self.blend_mode = blend_mode;
+
+        let result = f(self);
+
+        self.blend_mode = saved;
+
+        result
+    }
+
+    /// Restricts all drawing performed inside `f` to the interior of `path`, as determined by
+    /// `fill_rule`.
+    ///
+    /// The path is interpreted in the current coordinate space. Nested clips intersect with each
+    /// other.
+    pub fn with_clip<F, R>(&mut self, path: &Path, fill_rule: FillRule, f: F) -> R
+    where
+        F: FnOnce(&mut Canvas) -> R,
+    {
+        let index = self.clip_top;
+        if self.renderer.clip_masks.len() <= index {
+            self.renderer.clip_masks.push(ClipMask {
+                data: Vec::new(),
+                bounds: Bounds::empty(),
+            });
+        }
+
+        let mask_size = self.width * self.height;
+        if self.renderer.clip_masks[index].data.len() < mask_size {
+            self.renderer.clip_masks[index].data.resize(mask_size, 0.0);
+        }
+
+        let transform = self.transform;
+        let bounds = if path.is_empty() {
+            None
+        } else {
+            self.path_bounds(path, transform, Point::new(0.0, 0.0))
+        };
+
+        self.renderer.clip_masks[index].bounds = bounds.unwrap_or(Bounds::empty());
+
+        if let Some(bounds) = bounds {
+            let offset = self.begin(bounds);
+
+            flatten(path, transform, &mut |p1, p2| {
+                self.add_segment(p1 - offset, p2 - offset);
+            });
+
+            self.drain_segments();
+
+            let (parents, masks) = self.renderer.clip_masks.split_at_mut(index);
+            let mask = &mut masks[0];
+
+            for y in bounds.min_y..bounds.max_y {
+                let row_start = y * self.width;
+                mask.data[row_start + bounds.min_x..row_start + bounds.max_x].fill(0.0);
+            }
+
+            let data_start = bounds.min_y * self.width + bounds.min_x;
+            let parent = self.clip.map(|parent| &parents[parent].data[data_start..]);
+            self.renderer.rasterizer.finish(
+                fill_rule,
+                &mut Mask {
+                    data: &mut mask.data[data_start..],
+                    parent,
+                    stride: self.width,
+                },
+            );
+        }
+
+        let saved_clip = self.clip;
+        let saved_clip_top = self.clip_top;
+        self.clip = Some(index);
+        self.clip_top = index + 1;
+
+        let result = f(self);
+
+        self.clip = saved_clip;
+        self.clip_top = saved_clip_top;
+
+        result
+    }
+
+    /// Renders all drawing performed inside `f` into an offscreen layer, which is then composited
+    /// onto the canvas as a single image with the given opacity and blend mode.
+    ///
+    /// The active clip is applied when compositing the layer rather than to the individual drawing
+    /// operations inside it.
+    pub fn with_layer<F, R>(&mut self, opacity: f32, blend_mode: BlendMode, f: F) -> R
+    where
+        F: FnOnce(&mut Canvas) -> R,
+    {
+        let index = self.layer_top;
+        if self.renderer.layers.len() <= index {
+            self.renderer.layers.push(Vec::new());
+        }
+
+        let mut layer = std::mem::take(&mut self.renderer.layers[index]);
+        let layer_size = self.width * self.height;
+        if layer.len() < layer_size {
+            layer.resize(layer_size, 0);
+        }
+
+        let bounds = match self.clip {
+            Some(clip) => self.renderer.clip_masks[clip].bounds,
+            None => Bounds {
+                min_x: 0,
+                min_y: 0,
+                max_x: self.width,
+                max_y: self.height,
+            },
+        };
+
+        for y in bounds.min_y..bounds.max_y {
+            let row_start = y * self.width;
+            layer[row_start + bounds.min_x..row_start + bounds.max_x].fill(0);
+        }
+
+        let result = f(&mut Canvas {
+            renderer: self.renderer,
+            data: &mut layer[..layer_size],
+            width: self.width,
+            height: self.height,
+            transform: self.transform,
+            blend_mode: BlendMode::SrcOver,
+            clip: None,
+            clip_top: self.clip_top,
+            layer_top: index + 1,
+        });
+
+        if !bounds.is_empty() && opacity > 0.0 {
+            let data_start = bounds.min_y * self.width + bounds.min_x;
+            let clip = self.clip.map(|clip| &self.renderer.clip_masks[clip].data[data_start..]);
+            CompositeLayer {
+                src: &layer[data_start..],
+                dst: &mut self.data[data_start..],
+                clip,
+                stride: self.width,
+                width: bounds.max_x - bounds.min_x,
+                height: bounds.max_y - bounds.min_y,
+                opacity: opacity.min(1.0),
+                blend_mode,
+            }
+            .finish();
+        }
+
+        self.renderer.layers[index] = layer;
+
+        result
+    }
+
     pub fn clear(&mut self, color: Color) {
+        if let Some(clip) = self.clip {
+            let mask = &self.renderer.clip_masks[clip];
+            let bounds = mask.bounds;
+            for y in bounds.min_y..bounds.max_y {
+                let start = y * self.width + bounds.min_x;
+                let end = y * self.width + bounds.max_x;
+                for (pixel, &coverage) in
+                    self.data[start..end].iter_mut().zip(&mask.data[start..end])
+                {
+                    *pixel = lerp_pixel(coverage, *pixel, color);
+                }
+            }
+            return;
+        }
+
         for pixel in self.data.iter_mut() {
             *pixel = color.into();
         }
@@ -94,93 +347,159 @@ impl<'a> Canvas<'a> {
         self.renderer.segments.clear();
     }
 
-    pub fn fill_path(&mut self, path: &Path, transform: Affine, color: Color) {
-        if path.is_empty() {
-            return;
-        }
-
-        let transform = self.transform * transform;
-
+    /// Computes the pixel bounds of a path after transformation, dilated by `dilate` in each
+    /// direction, and clamped to the canvas and the active clip. Returns `None` if the bounds are
+    /// empty.
+    fn path_bounds(&self, path: &Path, transform: Affine, dilate: Point) -> Option<Bounds> {
         let mut min = Point::new(self.width as f32, self.height as f32);
         let mut max = Point::new(0.0, 0.0);
         for &point in &path.points {
             let transformed = transform * point;
-            min = min.min(transformed);
-            max = max.max(transformed);
+            min = min.min(transformed - dilate);
+            max = max.max(transformed + dilate);
         }
 
-        let min_x = (min.x as isize).max(0).min(self.width as isize) as usize;
-        let min_y = (min.y as isize).max(0).min(self.height as isize) as usize;
-        let max_x = ((max.x + 1.0) as isize).max(0).min(self.width as isize) as usize;
-        let max_y = ((max.y + 1.0) as isize).max(0).min(self.height as isize) as usize;
+        let mut bounds = Bounds {
+            min_x: (min.x as isize).max(0).min(self.width as isize) as usize,
+            min_y: (min.y as isize).max(0).min(self.height as isize) as usize,
+            max_x: ((max.x + 1.0) as isize).max(0).min(self.width as isize) as usize,
+            max_y: ((max.y + 1.0) as isize).max(0).min(self.height as isize) as usize,
+        };
 
-        if max_x <= min_x || max_y <= min_y {
-            return;
+        if let Some(clip) = self.clip {
+            bounds = bounds.intersect(&self.renderer.clip_masks[clip].bounds);
         }
 
-        let path_width = max_x - min_x;
-        let path_height = max_y - min_y;
+        if bounds.is_empty() {
+            return None;
+        }
 
-        let offset = Point::new(min_x as f32, min_y as f32);
+        Some(bounds)
+    }
 
-        self.renderer.rasterizer.set_size(path_width, path_height);
+    /// Prepares the rasterizer for a path covering the given bounds, and returns the offset to
+    /// subtract from segment coordinates.
+    fn begin(&mut self, bounds: Bounds) -> Point {
+        self.renderer
+            .rasterizer
+            .set_size(bounds.max_x - bounds.min_x, bounds.max_y - bounds.min_y);
 
-        flatten(path, transform, &mut |p1, p2| {
-            self.add_segment(p1 - offset, p2 - offset);
-        });
+        Point::new(bounds.min_x as f32, bounds.min_y as f32)
+    }
 
-        self.drain_segments();
+    /// Composites the rasterized path onto the canvas.
+    fn composite(&mut self, bounds: Bounds, fill_rule: FillRule, paint: Paint, transform: Affine) {
+        let offset = Point::new(bounds.min_x as f32, bounds.min_y as f32);
+        let shader = Shader::new(paint, Affine::translate(-offset.x, -offset.y) * transform);
 
-        let data_start = min_y * self.width + min_x;
-        self.renderer.rasterizer.finish(color, &mut self.data[data_start..], self.width);
+        let data_start = bounds.min_y * self.width + bounds.min_x;
+        let clip = self.clip.map(|clip| &self.renderer.clip_masks[clip].data[data_start..]);
+        self.renderer.rasterizer.finish(
+            fill_rule,
+            &mut Composite {
+                shader: &shader,
+                data: &mut self.data[data_start..],
+                clip,
+                stride: self.width,
+                blend_mode: self.blend_mode,
+            },
+        );
     }
 
-    pub fn stroke_path(&mut self, path: &Path, width: f32, transform: Affine, color: Color) {
+    /// Fills the interior of a path, as determined by `fill_rule`.
+    pub fn fill_path<'p>(
+        &mut self,
+        path: &Path,
+        fill_rule: FillRule,
+        transform: Affine,
+        paint: impl Into<Paint<'p>>,
+    ) {
         if path.is_empty() {
             return;
         }
 
         let transform = self.transform * transform;
 
-        let dilate_x = transform.linear() * width * Point::new(0.5, 0.0);
-        let dilate_y = transform.linear() * width * Point::new(0.0, 0.5);
-        let dilate_min = dilate_x.min(dilate_y);
-        let dilate_max = dilate_x.max(dilate_y);
+        let Some(bounds) = self.path_bounds(path, transform, Point::new(0.0, 0.0)) else {
+            return;
+        };
 
-        let mut min = Point::new(self.width as f32, self.height as f32);
-        let mut max = Point::new(0.0, 0.0);
-        for &point in &path.points {
-            let transformed = transform * point;
-            min = min.min(transformed + dilate_min);
-            max = max.max(transformed + dilate_max);
-        }
+        let offset = self.begin(bounds);
+
+        flatten(path, transform, &mut |p1, p2| {
+            self.add_segment(p1 - offset, p2 - offset);
+        });
 
-        let min_x = (min.x as isize).max(0).min(self.width as isize) as usize;
-        let min_y = (min.y as isize).max(0).min(self.height as isize) as usize;
-        let max_x = ((max.x + 1.0) as isize).max(0).min(self.width as isize) as usize;
-        let max_y = ((max.y + 1.0) as isize).max(0).min(self.height as isize) as usize;
+        self.drain_segments();
+
+        self.composite(bounds, fill_rule, paint.into(), transform);
+    }
 
-        if max_x <= min_x || max_y <= min_y {
+    pub fn stroke_path<'p>(
+        &mut self,
+        path: &Path,
+        style: &StrokeStyle,
+        transform: Affine,
+        paint: impl Into<Paint<'p>>,
+    ) {
+        if path.is_empty() {
             return;
         }
 
-        let path_width = max_x - min_x;
-        let path_height = max_y - min_y;
+        let transform = self.transform * transform;
+
+        let extent = style.width * style.extent();
+        let dilate_x = transform.linear() * extent * Point::new(1.0, 0.0);
+        let dilate_y = transform.linear() * extent * Point::new(0.0, 1.0);
+        let dilate = Point::new(
+            dilate_x.x.abs() + dilate_y.x.abs(),
+            dilate_x.y.abs() + dilate_y.y.abs(),
+        );
 
-        let offset = Point::new(min_x as f32, min_y as f32);
+        let Some(bounds) = self.path_bounds(path, transform, dilate) else {
+            return;
+        };
 
-        self.renderer.rasterizer.set_size(path_width, path_height);
+        let offset = self.begin(bounds);
 
-        stroke(path, width, transform, &mut |p1, p2| {
+        stroke(path, style, transform, &mut |p1, p2| {
             self.add_segment(p1 - offset, p2 - offset);
         });
 
         self.drain_segments();
 
-        let data_start = min_y * self.width + min_x;
-        self.renderer.rasterizer.finish(color, &mut self.data[data_start..], self.width);
+        self.composite(bounds, FillRule::NonZero, paint.into(), transform);
+    }
+
+    /// Draws an image with its top-left corner at the origin, covering one unit per pixel.
+    pub fn draw_image(&mut self, image: &Image, transform: Affine) {
+        let width = image.width() as f32;
+        let height = image.height() as f32;
+
+        let mut rect = Path::new();
+        rect.move_to(Point::new(0.0, 0.0));
+        rect.line_to(Point::new(width, 0.0));
+        rect.line_to(Point::new(width, height));
+        rect.line_to(Point::new(0.0, height));
+        rect.close();
+
+        let mut pattern = Pattern::new(image);
+        pattern.extend(Extend::Pad);
+
+        self.fill_path(&rect, FillRule::NonZero, transform, pattern);
     }
 
+    /// Fills a sequence of positioned glyphs.
+    ///
+    /// Glyph outlines are cached in the [`Renderer`]. When the combined transform consists only
+    /// of a translation and a uniform scale, glyphs are additionally rasterized once per size and
+    /// subpixel offset and drawn from the cached coverage masks.
+    ///
+    /// Color glyphs are drawn in their own colors, from the layers of the font's `COLR` table
+    /// using its selected palette (see [`Font::with_palette`]), or from the images of its `sbix` or
+    /// `CBDT` bitmap strikes. `color` is used for the layers which are defined to use the text
+    /// color. Compositing modes of `COLR` layers which have no equivalent [`BlendMode`] are drawn
+    /// with [`BlendMode::SrcOver`].
     pub fn fill_glyphs(
         &mut self,
         glyphs: &[Glyph],
@@ -189,55 +508,134 @@ impl<'a> Canvas<'a> {
         transform: Affine,
         color: Color,
     ) {
-        use rustybuzz::ttf_parser::{GlyphId, OutlineBuilder};
+        let scale = size / font.face.units_per_em() as f32;
 
-        struct Builder {
-            path: Path,
-            ascent: f32,
-        }
+        let device_transform = self.transform * transform;
+        let [a, b, _, c, d, _] = device_transform.coeffs();
+        let pixel_size = a * size;
+        let use_masks = b == 0.0 && c == 0.0 && a == d && a > 0.0 && pixel_size <= MAX_MASK_SIZE;
+        let has_color_glyphs = font.has_color_glyphs();
 
-        impl OutlineBuilder for Builder {
-            fn move_to(&mut self, x: f32, y: f32) {
-                self.path.move_to(Point::new(x, self.ascent - y));
+        for glyph in glyphs {
+            if has_color_glyphs && self.draw_color_glyph(glyph, font, size, transform, color) {
+                continue;
             }
 
-            fn line_to(&mut self, x: f32, y: f32) {
-                self.path.line_to(Point::new(x, self.ascent - y));
+            if use_masks {
+                let origin = device_transform * Point::new(glyph.x, glyph.y);
+                self.draw_glyph_mask(font, glyph.id, pixel_size, origin, color);
+                continue;
             }
 
-            fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
-                self.path.quadratic_to(
-                    Point::new(x1, self.ascent - y1),
-                    Point::new(x, self.ascent - y),
-                );
-            }
+            let Some(outline) = self.renderer.glyph_cache.outline(font, glyph.id) else {
+                continue;
+            };
 
-            fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
-                self.path.cubic_to(
-                    Point::new(x1, self.ascent - y1),
-                    Point::new(x2, self.ascent - y2),
-                    Point::new(x, self.ascent - y),
-                );
-            }
+            let transform = transform * Affine::translate(glyph.x, glyph.y) * Affine::scale(scale);
 
-            fn close(&mut self) {
-                self.path.close();
-            }
+            self.fill_path(&outline, FillRule::NonZero, transform, color);
         }
+    }
 
-        let scale = size / font.face.units_per_em() as f32;
+    /// Draws a glyph from the font's `COLR` layers or bitmap strikes. Returns `false` if the font
+    /// defines neither for the glyph, in which case it is drawn from its outline.
+    fn draw_color_glyph(
+        &mut self,
+        glyph: &Glyph,
+        font: &Font,
+        size: f32,
+        transform: Affine,
+        color: Color,
+    ) -> bool {
+        let upem = font.face.units_per_em() as f32;
+        let ascender = font.face.ascender() as f32;
+        let origin = transform * Affine::translate(glyph.x, glyph.y);
 
-        for glyph in glyphs {
-            let mut builder = Builder {
-                path: Path::new(),
-                ascent: font.face.ascender() as f32,
+        if font.face.is_color_glyph(GlyphId(glyph.id)) {
+            let Some(commands) =
+                color_glyph::record(font, glyph.id, color, &mut self.renderer.glyph_cache)
+            else {
+                return false;
             };
-            font.face.outline_glyph(GlyphId(glyph.id), &mut builder);
 
-            let transform = transform * Affine::translate(glyph.x, glyph.y) * Affine::scale(scale);
+            // Layers are in font units with y pointing up, relative to the glyph origin on the
+            // baseline.
+            let flip = Affine::new([1.0, 0.0, 0.0, 0.0, -1.0, ascender]);
+            let transform = origin * Affine::scale(size / upem) * flip;
+            self.with_transform(transform, |canvas| {
+                color_glyph::replay(canvas, &mut commands.iter());
+            });
+            return true;
+        }
+
+        // Choose the strike for the size of the glyph on the canvas.
+        let device_size = (self.transform * transform).determinant().abs().sqrt() * size;
+        let pixels_per_em = device_size.round().clamp(1.0, u16::MAX as f32) as u16;
+        let Some(bitmap) = self.renderer.glyph_cache.bitmap(font, glyph.id, pixels_per_em) else {
+            return false;
+        };
+
+        let image_height = bitmap.image.height() as f32;
+        let transform = origin
+            * Affine::translate(0.0, size * ascender / upem)
+            * Affine::scale(size / bitmap.pixels_per_em)
+            * Affine::translate(bitmap.x, -(bitmap.y + image_height));
+        self.draw_image(&bitmap.image, transform);
+        true
+    }
+
+    /// Draws a glyph from its cached coverage mask, with the glyph origin at the device-space
+    /// point `origin`.
+    fn draw_glyph_mask(&mut self, font: &Font, glyph: u16, size: f32, origin: Point, color: Color) {
+        let (x, subpixel_x) = quantize(origin.x);
+        let (y, subpixel_y) = quantize(origin.y);
+        let key = GlyphKey {
+            font: font.id,
+            glyph,
+            size: size.to_bits(),
+            subpixel_x,
+            subpixel_y,
+        };
 
-            self.fill_path(&builder.path, transform, color);
+        let renderer = &mut *self.renderer;
+        let Some(mask) = renderer.glyph_cache.mask(key, font, &mut renderer.rasterizer) else {
+            return;
+        };
+
+        let left = x + mask.left;
+        let top = y + mask.top;
+        let clamp = |value: isize, max: usize| value.clamp(0, max as isize) as usize;
+        let mut bounds = Bounds {
+            min_x: clamp(left, self.width),
+            min_y: clamp(top, self.height),
+            max_x: clamp(left + mask.width as isize, self.width),
+            max_y: clamp(top + mask.height as isize, self.height),
+        };
+        if let Some(clip) = self.clip {
+            bounds = bounds.intersect(&renderer.clip_masks[clip].bounds);
         }
+        if bounds.is_empty() {
+            return;
+        }
+
+        let shader = Shader::Solid(color);
+        let data_start = bounds.min_y * self.width + bounds.min_x;
+        let clip = self.clip.map(|clip| &renderer.clip_masks[clip].data[data_start..]);
+        let mask_start = (bounds.min_y as isize - top) as usize * mask.width
+            + (bounds.min_x as isize - left) as usize;
+        blit_mask(
+            &mask.data[mask_start..],
+            mask.width,
+            bounds.max_x - bounds.min_x,
+            bounds.max_y - bounds.min_y,
+            &mut Composite {
+                shader: &shader,
+                data: &mut self.data[data_start..],
+                clip,
+                stride: self.width,
+                blend_mode: self.blend_mode,
+            },
+        );
     }
 
     pub fn fill_text(
@@ -251,4 +649,833 @@ impl<'a> Canvas<'a> {
         let layout = TextLayout::new(text, font, size);
         self.fill_glyphs(layout.glyphs(), font, size, transform, color);
     }
+
+    /// Fills a line of text with the font, size, features, fallback fonts, color and
+    /// decorations of a text style.
+    pub fn fill_text_styled(&mut self, text: &str, style: &TextStyle, transform: Affine) {
+        let text = AttributedText::new(text, style);
+        let layout = TextLayout::attributed(&text, &ParagraphStyle::new());
+        self.fill_layout(&layout, transform);
+    }
+
+    /// Draws a text layout, using the font, size, color and decorations of the style of each of
+    /// its runs.
+    pub fn fill_layout(&mut self, layout: &TextLayout, transform: Affine) {
+        for run in layout.runs() {
+            let style = &layout.styles()[run.style];
+            let glyphs = &layout.glyphs()[run.glyphs.clone()];
+            self.fill_glyphs(glyphs, &run.font, style.size, transform, style.color);
+
+            // Decorations are positioned using the metrics of the style's primary font, so that
+            // they do not shift within a span which uses fallback fonts.
+            self.draw_decorations(
+                &DecorationRun {
+                    glyphs,
+                    font: &run.font,
+                    size: style.size,
+                    metrics: style.font.metrics(style.size),
+                    decoration: style.decoration,
+                    x: run.x,
+                    width: run.width,
+                    baseline: run.baseline,
+                },
+                transform,
+                style.color,
+            );
+        }
+    }
+
+    /// Draws decorations for a sequence of glyphs on a single baseline, such as those of a line
+    /// of a [`TextLayout`] or the glyphs passed to [`fill_glyphs`]. The decorations extend from
+    /// the leftmost glyph origin to the rightmost glyph advance.
+    ///
+    /// Underlines are interrupted where they would cross descenders.
+    ///
+    /// [`fill_glyphs`]: Canvas::fill_glyphs
+    pub fn decorate_glyphs(
+        &mut self,
+        glyphs: &[Glyph],
+        font: &Font,
+        size: f32,
+        decoration: TextDecoration,
+        transform: Affine,
+        color: Color,
+    ) {
+        let Some(first) = glyphs.first() else {
+            return;
+        };
+
+        let metrics = font.metrics(size);
+        let left = glyphs.iter().map(|glyph| glyph.x).fold(f32::INFINITY, f32::min);
+        let right = glyphs
+            .iter()
+            .map(|glyph| glyph.x + font.glyph_advance(glyph.id, size))
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        // Glyph outlines are positioned relative to the ascender of the font.
+        self.draw_decorations(
+            &DecorationRun {
+                glyphs,
+                font,
+                size,
+                metrics,
+                decoration,
+                x: left,
+                width: right - left,
+                baseline: first.y + metrics.ascent,
+            },
+            transform,
+            color,
+        );
+    }
+
+    fn draw_decorations(&mut self, run: &DecorationRun, transform: Affine, color: Color) {
+        let metrics = &run.metrics;
+        let decoration = run.decoration;
+        let end = run.x + run.width;
+
+        if decoration.underline {
+            let top = run.baseline - metrics.underline_position;
+            let thickness = metrics.underline_thickness;
+            let gaps = self.ink_gaps(run, top, top + thickness, thickness);
+
+            let mut start = run.x;
+            for (gap_start, gap_end) in gaps.into_iter().chain([(end, end)]) {
+                if gap_start > start {
+                    let rect = Path::rect(
+                        Point::new(start, top),
+                        gap_start.min(end) - start,
+                        thickness,
+                    );
+                    self.fill_path(&rect, FillRule::NonZero, transform, color);
+                }
+                start = start.max(gap_end);
+            }
+        }
+
+        // Each of the remaining decorations is given as the offset of its top edge above the
+        // baseline and its thickness.
+        let mut lines = Vec::new();
+        if decoration.strikethrough {
+            lines.push((metrics.strikeout_position, metrics.strikeout_thickness));
+        }
+        if decoration.overline {
+            lines.push((metrics.ascent, metrics.underline_thickness));
+        }
+
+        for (position, thickness) in lines {
+            let rect = Path::rect(
+                Point::new(run.x, run.baseline - position),
+                run.width,
+                thickness,
+            );
+            self.fill_path(&rect, FillRule::NonZero, transform, color);
+        }
+    }
+
+    /// Finds the horizontal extents, widened by `padding` on each side and sorted from left to
+    /// right, where the outlines of glyphs cross the band between `top` and `bottom`.
+    fn ink_gaps(
+        &mut self,
+        run: &DecorationRun,
+        top: f32,
+        bottom: f32,
+        padding: f32,
+    ) -> Vec<(f32, f32)> {
+        let scale = run.size / run.font.face.units_per_em() as f32;
+
+        let mut gaps = Vec::new();
+        for glyph in run.glyphs {
+            let Some(outline) = self.renderer.glyph_cache.outline(run.font, glyph.id) else {
+                continue;
+            };
+
+            let transform = Affine::translate(glyph.x, glyph.y) * Affine::scale(scale);
+            let mut min = f32::INFINITY;
+            let mut max = f32::NEG_INFINITY;
+            flatten(&outline, transform, &mut |p1, p2| {
+                if p1.y.max(p2.y) < top || p1.y.min(p2.y) > bottom {
+                    return;
+                }
+
+                // Clip the segment to the band.
+                let (t1, t2) = if p1.y == p2.y {
+                    (0.0, 1.0)
+                } else {
+                    let t1 = ((top - p1.y) / (p2.y - p1.y)).clamp(0.0, 1.0);
+                    let t2 = ((bottom - p1.y) / (p2.y - p1.y)).clamp(0.0, 1.0);
+                    (t1, t2)
+                };
+                for t in [t1, t2] {
+                    let x = p1.x + t * (p2.x - p1.x);
+                    min = min.min(x);
+                    max = max.max(x);
+                }
+            });
+
+            if min <= max {
+                gaps.push((min - padding, max + padding));
+            }
+        }
+
+        gaps.sort_by(|a, b| a.0.total_cmp(&b.0));
+        gaps
+    }
+}
+
+/// A sequence of glyphs on a baseline, and the extent and style of its decorations.
+struct DecorationRun<'a> {
+    glyphs: &'a [Glyph],
+    /// The font of the glyphs, whose outlines underlines skip over.
+    font: &'a Font,
+    size: f32,
+    /// The metrics which position the decorations.
+    metrics: FontMetrics,
+    decoration: TextDecoration,
+    x: f32,
+    width: f32,
+    baseline: f32,
+}
+
+/// Linearly interpolates from a premultiplied pixel value towards a color.
+#[inline]
+fn lerp_pixel(t: f32, pixel: u32, color: Color) -> u32 {
+    let src = crate::image::premultiply(color);
+    let lerp = |shift: u32| {
+        let dst = ((pixel >> shift) & 0xFF) as f32;
+        let src = ((src >> shift) & 0xFF) as f32;
+        ((dst + t * (src - dst) + 0.5) as u32) << shift
+    };
+    lerp(24) | lerp(16) | lerp(8) | lerp(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paint::{Filter, Gradient};
+    use crate::path::{LineCap, LineJoin};
+
+    fn rect(x0: f32, y0: f32, x1: f32, y1: f32) -> Path {
+        let mut path = Path::new();
+        path.move_to(Point::new(x0, y0));
+        path.line_to(Point::new(x1, y0));
+        path.line_to(Point::new(x1, y1));
+        path.line_to(Point::new(x0, y1));
+        path.close();
+        path
+    }
+
+    const WHITE: u32 = 0xFFFFFFFF;
+
+    #[test]
+    fn clip() {
+        let mut renderer = Renderer::new();
+        let mut data = vec![0; 16 * 16];
+        let mut canvas = renderer.canvas(&mut data, 16, 16);
+
+        canvas.with_clip(&rect(4.0, 4.0, 12.0, 12.0), FillRule::NonZero, |canvas| {
+            canvas.fill_path(
+                &rect(0.0, 0.0, 8.0, 16.0),
+                FillRule::NonZero,
+                Affine::id(),
+                Color::rgba(255, 255, 255, 255),
+            );
+        });
+
+        for y in 0..16 {
+            for x in 0..16 {
+                let inside = (4..8).contains(&x) && (4..12).contains(&y);
+                assert_eq!(
+                    data[y * 16 + x],
+                    if inside { WHITE } else { 0 },
+                    "({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn nested_clip() {
+        let mut renderer = Renderer::new();
+        let mut data = vec![0; 16 * 16];
+        let mut canvas = renderer.canvas(&mut data, 16, 16);
+
+        canvas.with_clip(&rect(0.0, 0.0, 10.0, 16.0), FillRule::NonZero, |canvas| {
+            canvas.with_clip(&rect(6.0, 0.0, 16.0, 16.0), FillRule::NonZero, |canvas| {
+                canvas.clear(Color::rgba(255, 255, 255, 255));
+            });
+        });
+
+        for y in 0..16 {
+            for x in 0..16 {
+                let inside = (6..10).contains(&x);
+                assert_eq!(
+                    data[y * 16 + x],
+                    if inside { WHITE } else { 0 },
+                    "({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn layer() {
+        let mut renderer = Renderer::new();
+        let mut data = vec![0; 16 * 16];
+        let mut canvas = renderer.canvas(&mut data, 16, 16);
+
+        canvas.with_layer(0.5, BlendMode::SrcOver, |canvas| {
+            let white = Color::rgba(255, 255, 255, 255);
+            canvas.fill_path(
+                &rect(0.0, 0.0, 12.0, 16.0),
+                FillRule::NonZero,
+                Affine::id(),
+                white,
+            );
+            canvas.fill_path(
+                &rect(4.0, 0.0, 16.0, 16.0),
+                FillRule::NonZero,
+                Affine::id(),
+                white,
+            );
+        });
+
+        // Overlapping shapes inside a layer should not blend with each other.
+        for &pixel in &data {
+            assert_eq!(pixel, 0x80808080);
+        }
+    }
+
+    #[test]
+    fn layer_clip() {
+        let mut renderer = Renderer::new();
+        let mut data = vec![0; 16 * 16];
+        let mut canvas = renderer.canvas(&mut data, 16, 16);
+
+        canvas.with_clip(&rect(4.0, 4.0, 12.0, 12.0), FillRule::NonZero, |canvas| {
+            canvas.with_layer(1.0, BlendMode::SrcOver, |canvas| {
+                canvas.clear(Color::rgba(255, 255, 255, 255));
+            });
+        });
+
+        for y in 0..16 {
+            for x in 0..16 {
+                let inside = (4..12).contains(&x) && (4..12).contains(&y);
+                assert_eq!(
+                    data[y * 16 + x],
+                    if inside { WHITE } else { 0 },
+                    "({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn blend_modes() {
+        let cases = [
+            (BlendMode::Clear, 0x00000000),
+            (BlendMode::Src, 0x80008000),
+            (BlendMode::SrcOver, 0xFF00807F),
+            (BlendMode::DstOver, 0xFF0000FF),
+            (BlendMode::SrcIn, 0x80008000),
+            (BlendMode::DstOut, 0x7F00007F),
+            (BlendMode::Xor, 0x7F00007F),
+            (BlendMode::Multiply, 0xFF00007F),
+            (BlendMode::Screen, 0xFF0080FF),
+            (BlendMode::Overlay, 0xFF0000FF),
+            (BlendMode::Darken, 0xFF00007F),
+            (BlendMode::Lighten, 0xFF0080FF),
+            (BlendMode::ColorDodge, 0xFF0000FF),
+            (BlendMode::ColorBurn, 0xFF0000FF),
+            (BlendMode::Difference, 0xFF0080FF),
+            (BlendMode::Additive, 0xFF0080FF),
+        ];
+
+        for (blend_mode, expected) in cases {
+            let mut renderer = Renderer::new();
+            let mut data = vec![0xFF0000FF; 8 * 8];
+            let mut canvas = renderer.canvas(&mut data, 8, 8);
+
+            canvas.with_blend_mode(blend_mode, |canvas| {
+                let green = Color::rgba(0, 255, 0, 128);
+                canvas.fill_path(
+                    &rect(0.0, 0.0, 8.0, 8.0),
+                    FillRule::NonZero,
+                    Affine::id(),
+                    green,
+                );
+            });
+
+            for &pixel in &data {
+                let diff =
+                    |shift: u32| ((pixel >> shift) & 0xFF).abs_diff((expected >> shift) & 0xFF);
+                assert!(
+                    diff(24) <= 1 && diff(16) <= 1 && diff(8) <= 1 && diff(0) <= 1,
+                    "{:?}: expected {:08X}, got {:08X}",
+                    blend_mode,
+                    expected,
+                    pixel,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fill_rules() {
+        let mut path = rect(0.0, 0.0, 16.0, 16.0);
+        path.move_to(Point::new(4.0, 4.0));
+        path.line_to(Point::new(12.0, 4.0));
+        path.line_to(Point::new(12.0, 12.0));
+        path.line_to(Point::new(4.0, 12.0));
+        path.close();
+
+        for fill_rule in [FillRule::NonZero, FillRule::EvenOdd] {
+            let mut renderer = Renderer::new();
+            let mut data = vec![0; 16 * 16];
+            let mut canvas = renderer.canvas(&mut data, 16, 16);
+
+            canvas.fill_path(
+                &path,
+                fill_rule,
+                Affine::id(),
+                Color::rgba(255, 255, 255, 255),
+            );
+
+            for y in 0..16 {
+                for x in 0..16 {
+                    let hole = (4..12).contains(&x) && (4..12).contains(&y);
+                    let expected = if hole && fill_rule == FillRule::EvenOdd {
+                        0
+                    } else {
+                        WHITE
+                    };
+                    assert_eq!(data[y * 16 + x], expected, "({}, {})", x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stroke_caps() {
+        let cases = [
+            (LineCap::Butt, false, false),
+            (LineCap::Round, true, false),
+            (LineCap::Square, true, true),
+        ];
+
+        for (cap, inside, corner) in cases {
+            let mut renderer = Renderer::new();
+            let mut data = vec![0; 16 * 16];
+            let mut canvas = renderer.canvas(&mut data, 16, 16);
+
+            let mut path = Path::new();
+            path.move_to(Point::new(6.0, 8.0));
+            path.line_to(Point::new(10.0, 8.0));
+
+            let mut style = StrokeStyle::new(8.0);
+            style.cap(cap);
+            canvas.stroke_path(&path, &style, Affine::id(), Color::rgba(255, 255, 255, 255));
+
+            assert_eq!(data[8 * 16 + 8], WHITE);
+            assert_eq!(data[8 * 16 + 3] == WHITE, inside, "{:?}", cap);
+            assert_eq!(data[4 * 16 + 2] == WHITE, corner, "{:?}", cap);
+        }
+    }
+
+    #[test]
+    fn stroke_joins() {
+        let cases = [
+            (LineJoin::Miter, 8.0, true),
+            (LineJoin::Miter, 1.0, false),
+            (LineJoin::Round, 8.0, false),
+            (LineJoin::Bevel, 8.0, false),
+        ];
+
+        for (join, miter_limit, corner) in cases {
+            let mut renderer = Renderer::new();
+            let mut data = vec![0; 16 * 16];
+            let mut canvas = renderer.canvas(&mut data, 16, 16);
+
+            let mut path = Path::new();
+            path.move_to(Point::new(4.0, 16.0));
+            path.line_to(Point::new(4.0, 4.0));
+            path.line_to(Point::new(16.0, 4.0));
+
+            let mut style = StrokeStyle::new(6.0);
+            style.join(join).miter_limit(miter_limit);
+            canvas.stroke_path(&path, &style, Affine::id(), Color::rgba(255, 255, 255, 255));
+
+            assert_eq!(data[4 * 16 + 4], WHITE);
+            assert_eq!(data[16 + 1] == WHITE, corner, "{:?}", join);
+            let rounded = corner || join == LineJoin::Round;
+            assert_eq!(data[2 * 16 + 2] == WHITE, rounded, "{:?}", join);
+        }
+    }
+
+    #[test]
+    fn stroke_dashes() {
+        let mut renderer = Renderer::new();
+        let mut data = vec![0; 16 * 16];
+        let mut canvas = renderer.canvas(&mut data, 16, 16);
+
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 8.0));
+        path.line_to(Point::new(16.0, 8.0));
+
+        let mut style = StrokeStyle::new(4.0);
+        style.dashes(&[3.0, 1.0], 1.0);
+        canvas.stroke_path(&path, &style, Affine::id(), Color::rgba(255, 255, 255, 255));
+
+        for x in 0..16 {
+            let on = x % 4 != 2;
+            assert_eq!(data[8 * 16 + x], if on { WHITE } else { 0 }, "{}", x);
+        }
+    }
+
+    #[test]
+    fn clip_even_odd() {
+        let mut renderer = Renderer::new();
+        let mut data = vec![0; 16 * 16];
+        let mut canvas = renderer.canvas(&mut data, 16, 16);
+
+        let mut path = rect(0.0, 0.0, 16.0, 16.0);
+        path.move_to(Point::new(4.0, 4.0));
+        path.line_to(Point::new(12.0, 4.0));
+        path.line_to(Point::new(12.0, 12.0));
+        path.line_to(Point::new(4.0, 12.0));
+        path.close();
+
+        canvas.with_clip(&path, FillRule::EvenOdd, |canvas| {
+            canvas.fill_path(
+                &rect(0.0, 0.0, 16.0, 16.0),
+                FillRule::NonZero,
+                Affine::id(),
+                Color::rgba(255, 255, 255, 255),
+            );
+        });
+
+        for y in 0..16 {
+            for x in 0..16 {
+                let hole = (4..12).contains(&x) && (4..12).contains(&y);
+                assert_eq!(
+                    data[y * 16 + x],
+                    if hole { 0 } else { WHITE },
+                    "({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    fn assert_close(pixel: u32, expected: u32, tolerance: u32) {
+        let diff = |shift: u32| ((pixel >> shift) & 0xFF).abs_diff((expected >> shift) & 0xFF);
+        assert!(
+            diff(24) <= tolerance
+                && diff(16) <= tolerance
+                && diff(8) <= tolerance
+                && diff(0) <= tolerance,
+            "expected {:08X}, got {:08X}",
+            expected,
+            pixel,
+        );
+    }
+
+    #[test]
+    fn linear_gradient() {
+        let mut renderer = Renderer::new();
+        let mut data = vec![0; 16 * 4];
+        let mut canvas = renderer.canvas(&mut data, 16, 4);
+
+        let mut gradient = Gradient::linear(Point::new(0.0, 0.0), Point::new(16.0, 0.0));
+        gradient
+            .stop(0.0, Color::rgba(255, 0, 0, 255))
+            .stop(1.0, Color::rgba(0, 0, 255, 255));
+        canvas.fill_path(
+            &rect(0.0, 0.0, 16.0, 4.0),
+            FillRule::NonZero,
+            Affine::id(),
+            &gradient,
+        );
+
+        for y in 0..4 {
+            for x in 0..16 {
+                let t = (x as f32 + 0.5) / 16.0;
+                let red = (255.0 * (1.0 - t)).round() as u32;
+                let blue = (255.0 * t).round() as u32;
+                assert_close(data[y * 16 + x], 0xFF000000 | red << 16 | blue, 2);
+            }
+        }
+    }
+
+    #[test]
+    fn radial_gradient() {
+        let mut renderer = Renderer::new();
+        let mut data = vec![0; 16 * 16];
+        let mut canvas = renderer.canvas(&mut data, 16, 16);
+
+        let mut gradient = Gradient::radial(Point::new(8.0, 8.0), 8.0);
+        gradient
+            .stop(0.0, Color::rgba(255, 255, 255, 255))
+            .stop(1.0, Color::rgba(0, 0, 0, 255));
+        canvas.fill_path(
+            &rect(0.0, 0.0, 16.0, 16.0),
+            FillRule::NonZero,
+            Affine::id(),
+            &gradient,
+        );
+
+        for y in 0..16 {
+            for x in 0..16 {
+                let dx = x as f32 + 0.5 - 8.0;
+                let dy = y as f32 + 0.5 - 8.0;
+                let t = ((dx * dx + dy * dy).sqrt() / 8.0).min(1.0);
+                let gray = (255.0 * (1.0 - t)).round() as u32;
+                assert_close(data[y * 16 + x], 0xFF000000 | gray * 0x010101, 2);
+            }
+        }
+    }
+
+    const BLACK: u32 = 0xFF000000;
+
+    #[test]
+    fn draw_image() {
+        let image = Image::from_data(2, 1, vec![BLACK, WHITE]);
+
+        // Pixel centers map onto texel centers, so a translated image is copied exactly.
+        let mut renderer = Renderer::new();
+        let mut data = vec![0; 8 * 2];
+        let mut canvas = renderer.canvas(&mut data, 8, 2);
+        canvas.draw_image(&image, Affine::translate(3.0, 1.0));
+        let mut expected = [0; 8 * 2];
+        expected[8 + 3] = BLACK;
+        expected[8 + 4] = WHITE;
+        assert_eq!(data, expected);
+
+        // Scaled up, neighboring texels are blended and the edges are padded.
+        let mut renderer = Renderer::new();
+        let mut data = vec![0; 8];
+        let mut canvas = renderer.canvas(&mut data, 8, 1);
+        canvas.draw_image(&image, Affine::translate(2.0, 0.0) * Affine::scale(2.0));
+        let expected = [0, 0, BLACK, 0xFF404040, 0xFFBFBFBF, WHITE, 0, 0];
+        for (&pixel, &expected) in data.iter().zip(expected.iter()) {
+            assert_close(pixel, expected, 1);
+        }
+    }
+
+    #[test]
+    fn pattern_extend() {
+        let image = Image::from_data(2, 1, vec![BLACK, WHITE]);
+
+        let cases = [
+            (Extend::Pad, [BLACK, WHITE, WHITE, WHITE, WHITE, WHITE]),
+            (Extend::Repeat, [BLACK, WHITE, BLACK, WHITE, BLACK, WHITE]),
+            (Extend::Reflect, [BLACK, WHITE, WHITE, BLACK, BLACK, WHITE]),
+        ];
+
+        for (extend, expected) in cases {
+            let mut renderer = Renderer::new();
+            let mut data = vec![0; 6];
+            let mut canvas = renderer.canvas(&mut data, 6, 1);
+
+            let mut pattern = Pattern::new(&image);
+            pattern.filter(Filter::Nearest).extend(extend);
+            canvas.fill_path(
+                &rect(0.0, 0.0, 6.0, 1.0),
+                FillRule::NonZero,
+                Affine::id(),
+                pattern,
+            );
+
+            assert_eq!(data, expected, "{:?}", extend);
+        }
+    }
+
+    fn font() -> Font {
+        Font::from_bytes(
+            include_bytes!("../examples/res/SourceSansPro-Regular.otf"),
+            0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn glyph_masks() {
+        let font = font();
+        let size = 16.0;
+        let scale = size / font.face.units_per_em() as f32;
+
+        // Glyphs on quarter-pixel positions are drawn from masks without any rounding.
+        let layout = TextLayout::new("Rag", &font, size);
+        let glyphs: Vec<Glyph> = layout
+            .glyphs()
+            .iter()
+            .enumerate()
+            .map(|(i, glyph)| Glyph {
+                id: glyph.id,
+                x: 10.25 * i as f32,
+                y: 0.5,
+            })
+            .collect();
+        let transform = Affine::translate(2.75, 3.0);
+
+        let mut renderer = Renderer::new();
+        let mut cached = vec![WHITE; 40 * 24];
+        let mut canvas = renderer.canvas(&mut cached, 40, 24);
+        for _ in 0..2 {
+            canvas.clear(Color::rgba(255, 255, 255, 255));
+            canvas.fill_glyphs(&glyphs, &font, size, transform, Color::rgba(0, 0, 0, 255));
+        }
+        assert_eq!(renderer.glyph_cache.mask_count(), glyphs.len());
+
+        let mut reference = vec![WHITE; 40 * 24];
+        let mut canvas = renderer.canvas(&mut reference, 40, 24);
+        for glyph in &glyphs {
+            let outline = canvas.renderer.glyph_cache.outline(&font, glyph.id).unwrap();
+            let transform = transform * Affine::translate(glyph.x, glyph.y) * Affine::scale(scale);
+            canvas.fill_path(
+                &outline,
+                FillRule::NonZero,
+                transform,
+                Color::rgba(0, 0, 0, 255),
+            );
+        }
+
+        assert!(reference.iter().any(|&pixel| pixel != WHITE));
+        for (&a, &b) in cached.iter().zip(&reference) {
+            assert!(((a & 0xFF) as i32 - (b & 0xFF) as i32).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn glyph_masks_clip() {
+        let font = font();
+
+        let mut renderer = Renderer::new();
+        let mut data = vec![0; 32 * 32];
+        let mut canvas = renderer.canvas(&mut data, 32, 32);
+        canvas.with_clip(&rect(0.0, 0.0, 32.0, 16.0), FillRule::NonZero, |canvas| {
+            canvas.fill_text(
+                "W",
+                &font,
+                40.0,
+                Affine::translate(-4.0, -8.0),
+                Color::rgba(255, 255, 255, 255),
+            );
+        });
+
+        assert!(data[..32 * 16].iter().any(|&pixel| pixel != 0));
+        assert!(data[32 * 16..].iter().all(|&pixel| pixel == 0));
+    }
+
+    #[test]
+    fn underline_skips_descenders() {
+        let font = font();
+        let size = 40.0;
+        let underline = TextDecoration {
+            underline: true,
+            ..TextDecoration::default()
+        };
+        let metrics = font.metrics(size);
+        let row = (metrics.ascent - metrics.underline_position).ceil() as usize;
+
+        // Counts the pixels covered by the underline alone along a fully covered row.
+        let covered = |text: &str| {
+            let layout = TextLayout::new(text, &font, size);
+            let mut renderer = Renderer::new();
+            let mut data = vec![WHITE; 100 * 60];
+            let mut canvas = renderer.canvas(&mut data, 100, 60);
+            canvas.decorate_glyphs(
+                layout.glyphs(),
+                &font,
+                size,
+                underline,
+                Affine::id(),
+                Color::rgba(255, 0, 0, 255),
+            );
+            assert_eq!(data[row * 100 + 2], 0xFFFF0000);
+            data[row * 100..(row + 1) * 100].iter().filter(|&&pixel| pixel != WHITE).count()
+        };
+
+        let (plain, descenders) = (covered("xxx"), covered("xgx"));
+        assert!(plain > 0);
+        assert!(descenders < plain - 5);
+    }
+
+    fn render_glyph(font: &Font, glyph: u16, size: f32, width: usize, height: usize) -> Vec<u32> {
+        let mut renderer = Renderer::new();
+        let mut data = vec![WHITE; width * height];
+        let mut canvas = renderer.canvas(&mut data, width, height);
+        let glyphs = [Glyph {
+            id: glyph,
+            x: 0.0,
+            y: 0.0,
+        }];
+        canvas.fill_glyphs(&glyphs, font, size, Affine::id(), Color::rgba(0, 0, 0, 255));
+        data
+    }
+
+    #[test]
+    fn colr_glyphs() {
+        // A version 0 glyph of a flag with three stripes, whose colors depend on the palette.
+        let v0 = Font::from_bytes(include_bytes!("../examples/res/TestCOLRv0.ttf"), 0).unwrap();
+        let stripes = |font: &Font| {
+            let data = render_glyph(font, 8, 60.0, 120, 80);
+            [13, 30, 48].map(|y| data[y * 120 + 40])
+        };
+        assert_eq!(v0.palette_count(), 2);
+        assert_eq!(stripes(&v0), [0xFF000000, 0xFFFF0000, 0xFFFFCC00]);
+        assert_eq!(
+            stripes(&v0.with_palette(1)),
+            [0xFF000000, 0xFFFFF000, 0xFF002395]
+        );
+        assert_eq!(stripes(&v0.with_palette(2)), stripes(&v0));
+
+        // A version 1 glyph filled with a linear gradient from red to blue.
+        let v1 = Font::from_bytes(include_bytes!("../examples/res/TestCOLRv1.ttf"), 0).unwrap();
+        let data = render_glyph(&v1, 9, 100.0, 100, 100);
+        let [left, middle, right] = [30, 50, 70].map(|x| Color::from(data[35 * 100 + x]));
+        assert!(left.r() > 200 && left.b() < 50);
+        assert!(middle.r() > 100 && middle.b() > 100);
+        assert!(right.r() < 50 && right.b() > 200);
+    }
+
+    #[test]
+    fn bitmap_glyphs() {
+        let size = 94.0;
+        for (data, glyph) in [
+            (&include_bytes!("../examples/res/TestCBDT.ttf")[..], 1),
+            (&include_bytes!("../examples/res/TestSbix.ttf")[..], 4),
+        ] {
+            let font = Font::from_bytes(data, 0).unwrap();
+            let data = render_glyph(&font, glyph, size, 160, 160);
+
+            // The image is drawn in color, scaled from its strike and positioned relative to
+            // the baseline.
+            let image = font.face.glyph_raster_image(GlyphId(glyph), size as u16).unwrap();
+            let scale = size / image.pixels_per_em as f32;
+            let left = scale * image.x as f32;
+            let top = font.metrics(size).ascent - scale * (image.y as f32 + image.height as f32);
+            let right = left + scale * image.width as f32;
+            let bottom = top + scale * image.height as f32;
+
+            let mut colored = false;
+            for (i, &pixel) in data.iter().enumerate() {
+                if pixel == WHITE {
+                    continue;
+                }
+                let (x, y) = ((i % 160) as f32, (i / 160) as f32);
+                assert!(x + 1.0 > left && x < right && y + 1.0 > top && y < bottom);
+                let color = Color::from(pixel);
+                colored |= color.r() as i32 - color.b() as i32 > 100;
+            }
+            assert!(colored);
+        }
+    }
 }
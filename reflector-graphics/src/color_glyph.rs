@@ -0,0 +1,453 @@
+use std::f32::consts::PI;
+use std::slice;
+
+use rustybuzz::ttf_parser::colr::{self, ClipBox, CompositeMode, GradientExtend};
+use rustybuzz::ttf_parser::{self, GlyphId, RasterGlyphImage, RasterImageFormat, RgbaColor};
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::geom::{Affine, Point, Rect};
+use crate::glyph_cache::GlyphCache;
+use crate::image::Image;
+use crate::paint::{BlendMode, Extend, Gradient};
+use crate::path::{FillRule, Path};
+use crate::text::Font;
+
+/// A drawing operation of a glyph from a font's `COLR` table.
+///
+/// Paths and gradients are in the glyph's design space, in font units with y pointing up.
+pub(crate) enum Command {
+    PushClip(Path),
+    PopClip,
+    PushLayer { opacity: f32, blend_mode: BlendMode },
+    PopLayer,
+    Fill(Path, ColorPaint),
+}
+
+pub(crate) enum ColorPaint {
+    Solid(Color),
+    Gradient(Gradient),
+}
+
+/// Converts the paint graph of a color glyph into a list of drawing operations, using the
+/// font's palette and `foreground` for the layers which use the text color. Returns `None` if
+/// the font has no color definition for the glyph.
+pub(crate) fn record(
+    font: &Font,
+    glyph: u16,
+    foreground: Color,
+    cache: &mut GlyphCache,
+) -> Option<Vec<Command>> {
+    // Gradient stops are resolved against the variation coordinates of the face, which only live
+    // as long as this borrow of it.
+    let face: &ttf_parser::Face = &font.face;
+    let mut recorder = Recorder {
+        font,
+        cache,
+        palette: font.palette,
+        coords: face.variation_coordinates(),
+        ascender: face.ascender() as f32,
+        transforms: Vec::new(),
+        outline: None,
+        clips: Vec::new(),
+        commands: Vec::new(),
+    };
+
+    let foreground = RgbaColor::new(
+        foreground.r(),
+        foreground.g(),
+        foreground.b(),
+        foreground.a(),
+    );
+    face.paint_color_glyph(GlyphId(glyph), font.palette, foreground, &mut recorder)?;
+
+    Some(recorder.commands)
+}
+
+/// Performs drawing operations on a canvas whose coordinate space is the glyph's design space.
+pub(crate) fn replay(canvas: &mut Canvas, commands: &mut slice::Iter<Command>) {
+    while let Some(command) = commands.next() {
+        match command {
+            Command::PushClip(path) => {
+                canvas.with_clip(path, FillRule::NonZero, |canvas| replay(canvas, commands));
+            }
+            Command::PushLayer {
+                opacity,
+                blend_mode,
+            } => {
+                canvas.with_layer(*opacity, *blend_mode, |canvas| replay(canvas, commands));
+            }
+            Command::PopClip | Command::PopLayer => return,
+            Command::Fill(path, ColorPaint::Solid(color)) => {
+                canvas.fill_path(path, FillRule::NonZero, Affine::id(), *color);
+            }
+            Command::Fill(path, ColorPaint::Gradient(gradient)) => {
+                canvas.fill_path(path, FillRule::NonZero, Affine::id(), gradient);
+            }
+        }
+    }
+}
+
+struct Recorder<'a, 'c> {
+    font: &'a Font,
+    cache: &'c mut GlyphCache,
+    palette: u16,
+    coords: &'a [ttf_parser::NormalizedCoordinate],
+    ascender: f32,
+    transforms: Vec<Affine>,
+    // The most recently outlined glyph, transformed into design space.
+    outline: Option<Path>,
+    // The bounds of each active clip.
+    clips: Vec<Rect>,
+    commands: Vec<Command>,
+}
+
+impl Recorder<'_, '_> {
+    #[inline]
+    fn transform(&self) -> Affine {
+        self.transforms.last().copied().unwrap_or(Affine::id())
+    }
+
+    fn push_clip_path(&mut self, path: Path) {
+        let empty = Rect::new(Point::new(0.0, 0.0), Point::new(0.0, 0.0));
+        let bounds = path.control_bounds().unwrap_or(empty);
+        // Paints are clipped by every enclosing clip, so the innermost one can be narrowed.
+        let bounds = match self.clips.last() {
+            Some(parent) => intersect(bounds, *parent),
+            None => bounds,
+        };
+        self.clips.push(bounds);
+        self.commands.push(Command::PushClip(path));
+    }
+}
+
+impl<'a> colr::Painter<'a> for Recorder<'a, '_> {
+    fn outline_glyph(&mut self, glyph_id: GlyphId) {
+        // Cached outlines have their origin at the ascender line with y pointing down.
+        let flip = Affine::new([1.0, 0.0, 0.0, 0.0, -1.0, self.ascender]);
+        let transform = self.transform() * flip;
+        self.outline = self
+            .cache
+            .outline(self.font, glyph_id.0)
+            .map(|outline| transformed(&outline, transform));
+    }
+
+    fn paint(&mut self, paint: colr::Paint<'a>) {
+        // Paints fill the innermost clip. Glyphs from version 0 tables have no clips, and paint
+        // the outline of each layer instead.
+        let path = match self.clips.last() {
+            Some(bounds) => Path::rect(bounds.min, bounds.width(), bounds.height()),
+            None => match &self.outline {
+                Some(outline) => outline.clone(),
+                None => return,
+            },
+        };
+
+        let gradient = match paint {
+            colr::Paint::Solid(color) => {
+                let paint = ColorPaint::Solid(to_color(color));
+                self.commands.push(Command::Fill(path, paint));
+                return;
+            }
+            colr::Paint::LinearGradient(gradient) => {
+                let stops = gradient.stops(self.palette, self.coords).map(to_stop).collect();
+                linear_gradient(&gradient, stops)
+            }
+            colr::Paint::RadialGradient(gradient) => {
+                let stops = gradient.stops(self.palette, self.coords).map(to_stop).collect();
+                radial_gradient(&gradient, stops)
+            }
+            colr::Paint::SweepGradient(gradient) => {
+                let stops = gradient.stops(self.palette, self.coords).map(to_stop).collect();
+                sweep_gradient(&gradient, stops)
+            }
+        };
+
+        if let Some(mut gradient) = gradient {
+            gradient.transform(self.transform());
+            let paint = ColorPaint::Gradient(gradient);
+            self.commands.push(Command::Fill(path, paint));
+        }
+    }
+
+    fn push_clip(&mut self) {
+        let path = self.outline.clone().unwrap_or_default();
+        self.push_clip_path(path);
+    }
+
+    fn push_clip_box(&mut self, clip_box: ClipBox) {
+        let rect = Path::rect(
+            Point::new(clip_box.x_min, clip_box.y_min),
+            clip_box.x_max - clip_box.x_min,
+            clip_box.y_max - clip_box.y_min,
+        );
+        self.push_clip_path(transformed(&rect, self.transform()));
+    }
+
+    fn pop_clip(&mut self) {
+        self.clips.pop();
+        self.commands.push(Command::PopClip);
+    }
+
+    fn push_layer(&mut self, mode: CompositeMode) {
+        // The destination mode keeps the backdrop and discards the layer.
+        let (opacity, blend_mode) = match mode {
+            CompositeMode::Destination => (0.0, BlendMode::SrcOver),
+            mode => (1.0, blend_mode(mode)),
+        };
+        self.commands.push(Command::PushLayer {
+            opacity,
+            blend_mode,
+        });
+    }
+
+    fn pop_layer(&mut self) {
+        self.commands.push(Command::PopLayer);
+    }
+
+    fn push_transform(&mut self, t: ttf_parser::Transform) {
+        let transform = Affine::new([t.a, t.c, t.e, t.b, t.d, t.f]);
+        self.transforms.push(self.transform() * transform);
+    }
+
+    fn pop_transform(&mut self) {
+        self.transforms.pop();
+    }
+}
+
+/// Maps a `COLR` composite mode to a blend mode. Modes without an equivalent fall back to
+/// source-over.
+fn blend_mode(mode: CompositeMode) -> BlendMode {
+    match mode {
+        CompositeMode::Clear => BlendMode::Clear,
+        CompositeMode::Source => BlendMode::Src,
+        CompositeMode::DestinationOver => BlendMode::DstOver,
+        CompositeMode::SourceIn => BlendMode::SrcIn,
+        CompositeMode::DestinationOut => BlendMode::DstOut,
+        CompositeMode::Xor => BlendMode::Xor,
+        CompositeMode::Plus => BlendMode::Additive,
+        CompositeMode::Screen => BlendMode::Screen,
+        CompositeMode::Overlay => BlendMode::Overlay,
+        CompositeMode::Darken => BlendMode::Darken,
+        CompositeMode::Lighten => BlendMode::Lighten,
+        CompositeMode::ColorDodge => BlendMode::ColorDodge,
+        CompositeMode::ColorBurn => BlendMode::ColorBurn,
+        CompositeMode::Difference => BlendMode::Difference,
+        CompositeMode::Multiply => BlendMode::Multiply,
+        _ => BlendMode::SrcOver,
+    }
+}
+
+/// Constructs a linear gradient from the three points which define a `COLR` linear gradient.
+fn linear_gradient(
+    gradient: &colr::LinearGradient,
+    mut stops: Vec<(f32, Color)>,
+) -> Option<Gradient> {
+    let p0 = Point::new(gradient.x0, gradient.y0);
+    let p1 = Point::new(gradient.x1, gradient.y1);
+    let p2 = Point::new(gradient.x2, gradient.y2);
+
+    // The gradient runs from p0 towards p1, with lines of constant color parallel to the line
+    // from p0 to p2. The end point is p1 projected onto the normal of that line.
+    let normal = Point::new(p2.y - p0.y, p0.x - p2.x);
+    let length_squared = normal.dot(normal);
+    let p3 = if length_squared > 0.0 {
+        p0 + (normal.dot(p1 - p0) / length_squared) * normal
+    } else {
+        p1
+    };
+
+    let (min, max) = normalize(&mut stops, gradient.extend)?;
+    let mut result = Gradient::linear(Point::lerp(min, p0, p3), Point::lerp(max, p0, p3));
+    finish(&mut result, stops, gradient.extend);
+    Some(result)
+}
+
+/// Constructs a radial gradient between two circles.
+///
+/// The circles interpolated between the two form a cone, which is drawn as a gradient from the
+/// apex of the cone to the larger of the circles.
+fn radial_gradient(
+    gradient: &colr::RadialGradient,
+    mut stops: Vec<(f32, Color)>,
+) -> Option<Gradient> {
+    let c0 = Point::new(gradient.x0, gradient.y0);
+    let c1 = Point::new(gradient.x1, gradient.y1);
+    let (r0, r1) = (gradient.r0, gradient.r1);
+    if stops.is_empty() {
+        return None;
+    }
+
+    let mut result = if r0 == r1 {
+        // The circles form a cylinder rather than a cone, which is approximated by the second
+        // circle.
+        Gradient::radial(c1, r1)
+    } else {
+        // The parameters of the apex of the cone and of the larger circle.
+        let apex = r0 / (r0 - r1);
+        let (outer, center, radius) = if r1 > r0 {
+            (1.0, c1, r1)
+        } else {
+            (0.0, c0, r0)
+        };
+        for stop in &mut stops {
+            stop.0 = (stop.0 - apex) / (outer - apex);
+        }
+        Gradient::focal(center, radius, Point::lerp(apex, c0, c1))
+    };
+
+    stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+    finish(&mut result, stops, gradient.extend);
+    Some(result)
+}
+
+/// Constructs a sweep gradient. `COLR` angles are counter-clockwise in a space with y pointing up,
+/// in units of 180°.
+fn sweep_gradient(
+    gradient: &colr::SweepGradient,
+    mut stops: Vec<(f32, Color)>,
+) -> Option<Gradient> {
+    let center = Point::new(gradient.center_x, gradient.center_y);
+    let start = PI * gradient.start_angle;
+    let end = PI * gradient.end_angle;
+
+    let (min, max) = normalize(&mut stops, gradient.extend)?;
+    let mut result = Gradient::sweep(
+        center,
+        start + min * (end - start),
+        start + max * (end - start),
+    );
+    finish(&mut result, stops, gradient.extend);
+    Some(result)
+}
+
+/// Sorts color stops and rescales their offsets to `[0, 1]`, since `COLR` color lines can extend
+/// past that range. Returns the range of the original offsets which was rescaled, or `None` if
+/// there are no stops.
+///
+/// Repeating gradients repeat the range covered by the stops. Padded gradients are rescaled from a
+/// range which also covers `[0, 1]`, which keeps the start and end angles of sweeps, so that the
+/// area outside of those angles is divided between their first and last colors.
+fn normalize(stops: &mut [(f32, Color)], extend: GradientExtend) -> Option<(f32, f32)> {
+    stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let (mut min, mut max) = (stops.first()?.0, stops.last()?.0);
+    if let GradientExtend::Pad = extend {
+        min = min.min(0.0);
+        max = max.max(1.0);
+    }
+
+    if max > min {
+        for stop in stops {
+            stop.0 = (stop.0 - min) / (max - min);
+        }
+        Some((min, max))
+    } else {
+        Some((0.0, 1.0))
+    }
+}
+
+fn finish(gradient: &mut Gradient, stops: Vec<(f32, Color)>, extend: GradientExtend) {
+    for (offset, color) in stops {
+        gradient.stop(offset, color);
+    }
+    gradient.extend(self::extend(extend));
+}
+
+fn extend(extend: GradientExtend) -> Extend {
+    match extend {
+        GradientExtend::Pad => Extend::Pad,
+        GradientExtend::Repeat => Extend::Repeat,
+        GradientExtend::Reflect => Extend::Reflect,
+    }
+}
+
+#[inline]
+fn to_color(color: RgbaColor) -> Color {
+    Color::rgba(color.red, color.green, color.blue, color.alpha)
+}
+
+#[inline]
+fn to_stop(stop: colr::ColorStop) -> (f32, Color) {
+    (stop.stop_offset, to_color(stop.color))
+}
+
+fn transformed(path: &Path, transform: Affine) -> Path {
+    let mut path = path.clone();
+    for point in &mut path.points {
+        *point = transform * *point;
+    }
+    path
+}
+
+fn intersect(a: Rect, b: Rect) -> Rect {
+    let min = a.min.max(b.min);
+    Rect::new(min, a.max.min(b.max).max(min))
+}
+
+/// A decoded glyph image from one of the bitmap strikes of a font.
+pub struct GlyphBitmap {
+    pub image: Image,
+    /// The offset of the bottom-left corner of the image from the glyph origin on the baseline,
+    /// in pixels of the strike with y pointing up.
+    pub x: f32,
+    pub y: f32,
+    /// The size of the strike the image belongs to.
+    pub pixels_per_em: f32,
+}
+
+impl GlyphBitmap {
+    /// Decodes a color glyph image from an `sbix` or `CBDT` table. Returns `None` for images in
+    /// other formats, such as the monochrome bitmaps of `EBDT` tables.
+    pub(crate) fn decode(raster: RasterGlyphImage) -> Option<GlyphBitmap> {
+        let image = match raster.format {
+            RasterImageFormat::PNG => decode_png(raster.data)?,
+            RasterImageFormat::BitmapPremulBgra32 => {
+                let (width, height) = (raster.width as usize, raster.height as usize);
+                let data: Vec<u32> = raster
+                    .data
+                    .chunks_exact(4)
+                    .map(|p| u32::from_le_bytes([p[0], p[1], p[2], p[3]]))
+                    .collect();
+                if data.len() != width * height {
+                    return None;
+                }
+                Image::from_data(width, height, data)
+            }
+            _ => return None,
+        };
+
+        Some(GlyphBitmap {
+            image,
+            x: raster.x as f32,
+            y: raster.y as f32,
+            pixels_per_em: raster.pixels_per_em.max(1) as f32,
+        })
+    }
+}
+
+fn decode_png(data: &[u8]) -> Option<Image> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().ok()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).ok()?;
+    let pixels = &buffer[..info.buffer_size()];
+
+    let rgba: Vec<u8> = match info.color_type {
+        png::ColorType::Rgba => pixels.to_vec(),
+        png::ColorType::Rgb => {
+            pixels.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect()
+        }
+        png::ColorType::GrayscaleAlpha => {
+            pixels.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect()
+        }
+        png::ColorType::Grayscale => pixels.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+        png::ColorType::Indexed => return None,
+    };
+
+    Some(Image::from_rgba8(
+        info.width as usize,
+        info.height as usize,
+        &rgba,
+    ))
+}
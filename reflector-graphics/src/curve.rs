@@ -0,0 +1,595 @@
+use crate::geom::{Affine, Point};
+
+pub trait Curve: Sized {
+    fn transform(&self, transform: Affine) -> Self;
+
+    fn start(&self) -> Point;
+    fn end(&self) -> Point;
+    fn eval(&self, t: f32) -> Point;
+
+    /// Returns whether all control points of the curve coincide.
+    fn is_point(&self) -> bool;
+
+    fn tangent(&self, t: f32) -> Point;
+    #[allow(unused)]
+    fn start_tangent(&self) -> Point;
+    #[allow(unused)]
+    fn end_tangent(&self) -> Point;
+
+    fn segments_for_tolerance(&self, tolerance: f32) -> usize;
+
+    fn derivative(&self, t: f32) -> Point;
+
+    /// Calls `f` with each parameter value in the open interval (0, 1) at which the derivative of
+    /// the coordinate selected by `coord` vanishes.
+    fn extrema(&self, coord: fn(Point) -> f32, f: &mut impl FnMut(f32));
+
+    /// Splits the curve into two curves at the parameter value `t`.
+    fn split(&self, t: f32) -> (Self, Self);
+
+    /// Constructs the same curve traversed in the opposite direction.
+    fn reverse(&self) -> Self;
+}
+
+/// Extracts the part of a curve between two parameter values. If `t0 > t1`, the result runs in
+/// the opposite direction to the original curve.
+pub fn subcurve<C: Curve>(curve: &C, t0: f32, t1: f32) -> C {
+    if t0 > t1 {
+        return subcurve(curve, t1, t0).reverse();
+    }
+
+    let (_, right) = curve.split(t0);
+    if t0 >= 1.0 {
+        return right;
+    }
+    let (left, _) = right.split((t1 - t0) / (1.0 - t0));
+    left
+}
+
+#[inline]
+pub fn x(point: Point) -> f32 {
+    point.x
+}
+
+#[inline]
+pub fn y(point: Point) -> f32 {
+    point.y
+}
+
+#[derive(Copy, Clone)]
+pub struct Line {
+    pub p0: Point,
+    pub p1: Point,
+}
+
+impl Curve for Line {
+    #[inline]
+    fn transform(&self, transform: Affine) -> Self {
+        Line {
+            p0: transform * self.p0,
+            p1: transform * self.p1,
+        }
+    }
+
+    #[inline]
+    fn start(&self) -> Point {
+        self.p0
+    }
+
+    #[inline]
+    fn end(&self) -> Point {
+        self.p1
+    }
+
+    #[inline]
+    fn is_point(&self) -> bool {
+        self.p0 == self.p1
+    }
+
+    #[inline]
+    fn eval(&self, t: f32) -> Point {
+        Point::lerp(t, self.p0, self.p1)
+    }
+
+    #[inline]
+    fn start_tangent(&self) -> Point {
+        self.p1 - self.p0
+    }
+
+    #[inline]
+    fn end_tangent(&self) -> Point {
+        self.p1 - self.p0
+    }
+
+    #[inline]
+    fn tangent(&self, _t: f32) -> Point {
+        self.p1 - self.p0
+    }
+
+    #[inline]
+    fn segments_for_tolerance(&self, _tolerance: f32) -> usize {
+        1
+    }
+
+    #[inline]
+    fn derivative(&self, _t: f32) -> Point {
+        self.p1 - self.p0
+    }
+
+    #[inline]
+    fn extrema(&self, _coord: fn(Point) -> f32, _f: &mut impl FnMut(f32)) {}
+
+    #[inline]
+    fn split(&self, t: f32) -> (Self, Self) {
+        let mid = self.eval(t);
+        (
+            Line {
+                p0: self.p0,
+                p1: mid,
+            },
+            Line {
+                p0: mid,
+                p1: self.p1,
+            },
+        )
+    }
+
+    #[inline]
+    fn reverse(&self) -> Self {
+        Line {
+            p0: self.p1,
+            p1: self.p0,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Quadratic {
+    pub p0: Point,
+    pub p1: Point,
+    pub p2: Point,
+}
+
+impl Curve for Quadratic {
+    #[inline]
+    fn transform(&self, transform: Affine) -> Self {
+        Quadratic {
+            p0: transform * self.p0,
+            p1: transform * self.p1,
+            p2: transform * self.p2,
+        }
+    }
+
+    #[inline]
+    fn start(&self) -> Point {
+        self.p0
+    }
+
+    #[inline]
+    fn end(&self) -> Point {
+        self.p2
+    }
+
+    #[inline]
+    fn is_point(&self) -> bool {
+        self.p0 == self.p1 && self.p1 == self.p2
+    }
+
+    #[inline]
+    fn eval(&self, t: f32) -> Point {
+        let p01 = Point::lerp(t, self.p0, self.p1);
+        let p12 = Point::lerp(t, self.p1, self.p2);
+        Point::lerp(t, p01, p12)
+    }
+
+    #[inline]
+    fn start_tangent(&self) -> Point {
+        self.p1 - self.p0
+    }
+
+    #[inline]
+    fn end_tangent(&self) -> Point {
+        self.p2 - self.p1
+    }
+
+    #[inline]
+    fn tangent(&self, t: f32) -> Point {
+        Point::lerp(t, self.p1 - self.p0, self.p2 - self.p1)
+    }
+
+    #[inline]
+    fn segments_for_tolerance(&self, tolerance: f32) -> usize {
+        let dt = ((4.0 * tolerance) / (self.p0 - 2.0 * self.p1 + self.p2).length()).sqrt();
+
+        dt.recip().ceil() as usize
+    }
+
+    #[inline]
+    fn derivative(&self, t: f32) -> Point {
+        2.0 * self.tangent(t)
+    }
+
+    #[inline]
+    fn extrema(&self, coord: fn(Point) -> f32, f: &mut impl FnMut(f32)) {
+        let (a, b, c) = (coord(self.p0), coord(self.p1), coord(self.p2));
+        let denom = a - 2.0 * b + c;
+        if denom != 0.0 {
+            let t = (a - b) / denom;
+            if t > 0.0 && t < 1.0 {
+                f(t);
+            }
+        }
+    }
+
+    #[inline]
+    fn split(&self, t: f32) -> (Self, Self) {
+        let p01 = Point::lerp(t, self.p0, self.p1);
+        let p12 = Point::lerp(t, self.p1, self.p2);
+        let mid = Point::lerp(t, p01, p12);
+        (
+            Quadratic {
+                p0: self.p0,
+                p1: p01,
+                p2: mid,
+            },
+            Quadratic {
+                p0: mid,
+                p1: p12,
+                p2: self.p2,
+            },
+        )
+    }
+
+    #[inline]
+    fn reverse(&self) -> Self {
+        Quadratic {
+            p0: self.p2,
+            p1: self.p1,
+            p2: self.p0,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct Cubic {
+    pub p0: Point,
+    pub p1: Point,
+    pub p2: Point,
+    pub p3: Point,
+}
+
+impl Curve for Cubic {
+    #[inline]
+    fn transform(&self, transform: Affine) -> Self {
+        Cubic {
+            p0: transform * self.p0,
+            p1: transform * self.p1,
+            p2: transform * self.p2,
+            p3: transform * self.p3,
+        }
+    }
+
+    #[inline]
+    fn start(&self) -> Point {
+        self.p0
+    }
+
+    #[inline]
+    fn end(&self) -> Point {
+        self.p3
+    }
+
+    #[inline]
+    fn is_point(&self) -> bool {
+        self.p0 == self.p1 && self.p1 == self.p2 && self.p2 == self.p3
+    }
+
+    #[inline]
+    fn eval(&self, t: f32) -> Point {
+        let p01 = Point::lerp(t, self.p0, self.p1);
+        let p12 = Point::lerp(t, self.p1, self.p2);
+        let p23 = Point::lerp(t, self.p2, self.p3);
+        let p012 = Point::lerp(t, p01, p12);
+        let p123 = Point::lerp(t, p12, p23);
+        Point::lerp(t, p012, p123)
+    }
+
+    #[inline]
+    fn tangent(&self, t: f32) -> Point {
+        let t1 = Point::lerp(t, self.p1 - self.p0, self.p2 - self.p1);
+        let t2 = Point::lerp(t, self.p2 - self.p1, self.p3 - self.p2);
+        Point::lerp(t, t1, t2)
+    }
+
+    #[inline]
+    fn start_tangent(&self) -> Point {
+        self.p1 - self.p0
+    }
+
+    #[inline]
+    fn end_tangent(&self) -> Point {
+        self.p3 - self.p2
+    }
+
+    #[inline]
+    fn segments_for_tolerance(&self, tolerance: f32) -> usize {
+        let a = -1.0 * self.p0 + 3.0 * self.p1 - 3.0 * self.p2 + self.p3;
+        let b = 3.0 * (self.p0 - 2.0 * self.p1 + self.p2);
+        let conc = b.length().max((a + b).length());
+        let dt = ((8.0f32.sqrt() * tolerance) / conc).sqrt();
+
+        dt.recip().ceil() as usize
+    }
+
+    #[inline]
+    fn derivative(&self, t: f32) -> Point {
+        3.0 * self.tangent(t)
+    }
+
+    #[inline]
+    fn extrema(&self, coord: fn(Point) -> f32, f: &mut impl FnMut(f32)) {
+        let (a, b, c, d) = (
+            coord(self.p0),
+            coord(self.p1),
+            coord(self.p2),
+            coord(self.p3),
+        );
+        solve_quadratic(
+            -a + 3.0 * b - 3.0 * c + d,
+            2.0 * (a - 2.0 * b + c),
+            b - a,
+            &mut |t| {
+                if t > 0.0 && t < 1.0 {
+                    f(t);
+                }
+            },
+        );
+    }
+    #[inline]
+    fn split(&self, t: f32) -> (Self, Self) {
+        let p01 = Point::lerp(t, self.p0, self.p1);
+        let p12 = Point::lerp(t, self.p1, self.p2);
+        let p23 = Point::lerp(t, self.p2, self.p3);
+        let p012 = Point::lerp(t, p01, p12);
+        let p123 = Point::lerp(t, p12, p23);
+        let mid = Point::lerp(t, p012, p123);
+        (
+            Cubic {
+                p0: self.p0,
+                p1: p01,
+                p2: p012,
+                p3: mid,
+            },
+            Cubic {
+                p0: mid,
+                p1: p123,
+                p2: p23,
+                p3: self.p3,
+            },
+        )
+    }
+
+    #[inline]
+    fn reverse(&self) -> Self {
+        Cubic {
+            p0: self.p3,
+            p1: self.p2,
+            p2: self.p1,
+            p3: self.p0,
+        }
+    }
+}
+
+/// Calls `f` with each real root of `a t² + b t + c`.
+#[inline]
+pub fn solve_quadratic(a: f32, b: f32, c: f32, f: &mut impl FnMut(f32)) {
+    let scale = a.abs().max(b.abs()).max(c.abs());
+    if scale == 0.0 {
+        return;
+    }
+
+    if a.abs() <= 1e-6 * scale {
+        if b != 0.0 {
+            f(-c / b);
+        }
+        return;
+    }
+
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return;
+    }
+
+    // Avoid cancellation by computing the root with the larger magnitude first.
+    let q = -0.5 * (b + b.signum() * disc.sqrt());
+    if q == 0.0 {
+        f(0.0);
+        return;
+    }
+
+    let t0 = q / a;
+    let t1 = c / q;
+    f(t0);
+    if t1 != t0 {
+        f(t1);
+    }
+}
+
+/// A segment of a path.
+#[derive(Copy, Clone)]
+pub enum Segment {
+    Line(Line),
+    Quadratic(Quadratic),
+    Cubic(Cubic),
+}
+
+macro_rules! dispatch {
+    ($segment:expr, $curve:ident => $body:expr) => {
+        match $segment {
+            Segment::Line($curve) => $body,
+            Segment::Quadratic($curve) => $body,
+            Segment::Cubic($curve) => $body,
+        }
+    };
+}
+
+impl Curve for Segment {
+    #[inline]
+    fn transform(&self, transform: Affine) -> Self {
+        match self {
+            Segment::Line(line) => Segment::Line(line.transform(transform)),
+            Segment::Quadratic(quadratic) => Segment::Quadratic(quadratic.transform(transform)),
+            Segment::Cubic(cubic) => Segment::Cubic(cubic.transform(transform)),
+        }
+    }
+
+    #[inline]
+    fn start(&self) -> Point {
+        dispatch!(self, curve => curve.start())
+    }
+
+    #[inline]
+    fn end(&self) -> Point {
+        dispatch!(self, curve => curve.end())
+    }
+
+    #[inline]
+    fn is_point(&self) -> bool {
+        dispatch!(self, curve => curve.is_point())
+    }
+
+    #[inline]
+    fn eval(&self, t: f32) -> Point {
+        dispatch!(self, curve => curve.eval(t))
+    }
+
+    #[inline]
+    fn tangent(&self, t: f32) -> Point {
+        dispatch!(self, curve => curve.tangent(t))
+    }
+
+    #[inline]
+    fn start_tangent(&self) -> Point {
+        dispatch!(self, curve => curve.start_tangent())
+    }
+
+    #[inline]
+    fn end_tangent(&self) -> Point {
+        dispatch!(self, curve => curve.end_tangent())
+    }
+
+    #[inline]
+    fn segments_for_tolerance(&self, tolerance: f32) -> usize {
+        dispatch!(self, curve => curve.segments_for_tolerance(tolerance))
+    }
+
+    #[inline]
+    fn derivative(&self, t: f32) -> Point {
+        dispatch!(self, curve => curve.derivative(t))
+    }
+
+    #[inline]
+    fn extrema(&self, coord: fn(Point) -> f32, f: &mut impl FnMut(f32)) {
+        dispatch!(self, curve => curve.extrema(coord, f))
+    }
+    #[inline]
+    fn split(&self, t: f32) -> (Self, Self) {
+        match self {
+            Segment::Line(line) => {
+                let (left, right) = line.split(t);
+                (Segment::Line(left), Segment::Line(right))
+            }
+            Segment::Quadratic(quadratic) => {
+                let (left, right) = quadratic.split(t);
+                (Segment::Quadratic(left), Segment::Quadratic(right))
+            }
+            Segment::Cubic(cubic) => {
+                let (left, right) = cubic.split(t);
+                (Segment::Cubic(left), Segment::Cubic(right))
+            }
+        }
+    }
+
+    #[inline]
+    fn reverse(&self) -> Self {
+        match self {
+            Segment::Line(line) => Segment::Line(line.reverse()),
+            Segment::Quadratic(quadratic) => Segment::Quadratic(quadratic.reverse()),
+            Segment::Cubic(cubic) => Segment::Cubic(cubic.reverse()),
+        }
+    }
+}
+
+// Nodes and weights for 8-point Gauss-Legendre quadrature on [-1, 1].
+const GAUSS_LEGENDRE: [(f32, f32); 8] = [
+    (-0.960_289_9, 0.101_228_54),
+    (-0.796_666_5, 0.222_381_03),
+    (-0.525_532_4, 0.313_706_65),
+    (-0.183_434_64, 0.362_683_8),
+    (0.183_434_64, 0.362_683_8),
+    (0.525_532_4, 0.313_706_65),
+    (0.796_666_5, 0.222_381_03),
+    (0.960_289_9, 0.101_228_54),
+];
+
+#[inline]
+fn gauss_legendre<C: Curve>(curve: &C, t0: f32, t1: f32) -> f32 {
+    let half = 0.5 * (t1 - t0);
+    let mid = 0.5 * (t0 + t1);
+
+    let mut sum = 0.0;
+    for (x, w) in GAUSS_LEGENDRE {
+        sum += w * curve.derivative(mid + half * x).length();
+    }
+
+    half * sum
+}
+
+fn arc_length_adaptive<C: Curve>(curve: &C, t0: f32, t1: f32, whole: f32, depth: usize) -> f32 {
+    let mid = 0.5 * (t0 + t1);
+    let left = gauss_legendre(curve, t0, mid);
+    let right = gauss_legendre(curve, mid, t1);
+
+    let sum = left + right;
+    if depth == 0 || (sum - whole).abs() <= 1e-5 * sum {
+        return sum;
+    }
+
+    arc_length_adaptive(curve, t0, mid, left, depth - 1)
+        + arc_length_adaptive(curve, mid, t1, right, depth - 1)
+}
+
+/// Computes the arc length of a curve between two parameter values.
+pub fn arc_length<C: Curve>(curve: &C, t0: f32, t1: f32) -> f32 {
+    let whole = gauss_legendre(curve, t0, t1);
+    arc_length_adaptive(curve, t0, t1, whole, 8)
+}
+
+/// Finds the parameter value at which the arc length from the start of the curve reaches
+/// `distance`, given the total arc length `length`.
+pub fn inverse_arc_length<C: Curve>(curve: &C, distance: f32, length: f32) -> f32 {
+    if distance <= 0.0 || length <= 0.0 {
+        return 0.0;
+    }
+    if distance >= length {
+        return 1.0;
+    }
+
+    // Bisection, narrowing the interval incrementally so that each step only integrates over the
+    // part of the curve that changed.
+    let mut t0 = 0.0;
+    let mut t1 = 1.0;
+    let mut length0 = 0.0;
+    for _ in 0..24 {
+        let mid = 0.5 * (t0 + t1);
+        let length_mid = length0 + arc_length(curve, t0, mid);
+        if length_mid < distance {
+            t0 = mid;
+            length0 = length_mid;
+        } else {
+            t1 = mid;
+        }
+    }
+
+    0.5 * (t0 + t1)
+}
@@ -0,0 +1,336 @@
+use std::ops::Range;
+
+use unicode_segmentation::{GraphemeCursor, UnicodeSegmentation};
+
+use crate::geom::{Point, Rect};
+use crate::paragraph::is_line_break;
+use crate::text::{Cluster, TextLayout};
+
+/// The position of a caret in a [`TextLayout`], as a vertical line spanning the height of a line
+/// of text.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Caret {
+    pub x: f32,
+    /// The vertical offset of the top of the caret.
+    pub y: f32,
+    pub height: f32,
+}
+
+impl TextLayout {
+    /// Finds the index of the line containing a byte offset of the source text.
+    ///
+    /// An offset at the end of a line which was wrapped belongs to the start of the next line.
+    pub fn line_for_index(&self, index: usize) -> usize {
+        self.lines.partition_point(|line| line.text.start <= index).saturating_sub(1)
+    }
+
+    /// Finds the byte offset of the start of the line containing a byte offset.
+    #[inline]
+    pub fn line_start(&self, index: usize) -> usize {
+        self.lines.get(self.line_for_index(index)).map_or(0, |line| line.text.start)
+    }
+
+    /// Finds the last caret position on the line containing a byte offset, which is before the
+    /// line break ending the line, or before the last character of a line which was wrapped.
+    #[inline]
+    pub fn line_end(&self, index: usize) -> usize {
+        if self.lines.is_empty() {
+            return self.text.len();
+        }
+        self.caret_end(self.line_for_index(index))
+    }
+
+    /// Finds the grapheme cluster boundary following a byte offset, or the end of the text.
+    pub fn next_grapheme(&self, index: usize) -> usize {
+        let mut cursor = GraphemeCursor::new(index.min(self.text.len()), self.text.len(), true);
+        cursor.next_boundary(&self.text, 0).ok().flatten().unwrap_or(self.text.len())
+    }
+
+    /// Finds the grapheme cluster boundary preceding a byte offset, or the start of the text.
+    pub fn previous_grapheme(&self, index: usize) -> usize {
+        let mut cursor = GraphemeCursor::new(index.min(self.text.len()), self.text.len(), true);
+        cursor.prev_boundary(&self.text, 0).ok().flatten().unwrap_or(0)
+    }
+
+    /// Finds the byte offset of the caret position closest to a point.
+    ///
+    /// Points above or below the layout map to the first or last line, and points beyond either
+    /// end of a line map to the position at that end. Positions within a cluster, such as a
+    /// ligature, are assumed to divide it evenly between its grapheme clusters.
+    pub fn hit_test_point(&self, x: f32, y: f32) -> usize {
+        let Some(last) = self.lines.len().checked_sub(1) else {
+            return 0;
+        };
+        let index = self.lines.iter().position(|line| y < line.y + line.height).unwrap_or(last);
+        let line = &self.lines[index];
+        let end = self.caret_end(index);
+
+        // Positions at direction boundaries can belong to the clusters on either side, so every
+        // cluster on the line is considered.
+        let mut best = line.text.start;
+        let mut best_distance = f32::INFINITY;
+        for cluster in &self.clusters[line.clusters.clone()] {
+            for offset in self.boundaries(cluster).filter(|&offset| offset <= end) {
+                let distance = (self.cluster_x(cluster, offset) - x).abs();
+                if distance < best_distance {
+                    best = offset;
+                    best_distance = distance;
+                }
+            }
+        }
+        best
+    }
+
+    /// Finds the position of the caret before the character at a byte offset.
+    pub fn caret_position(&self, index: usize) -> Caret {
+        let Some(line) = self.lines.get(self.line_for_index(index)) else {
+            return Caret {
+                x: 0.0,
+                y: 0.0,
+                height: 0.0,
+            };
+        };
+
+        let clusters = &self.clusters[line.clusters.clone()];
+        let cluster = clusters
+            .iter()
+            .find(|cluster| cluster.text.contains(&index))
+            .or_else(|| clusters.iter().find(|cluster| cluster.text.end == index))
+            .or_else(|| clusters.iter().max_by_key(|cluster| cluster.text.end));
+        let x = match cluster {
+            Some(cluster) => {
+                let index = index.clamp(cluster.text.start, cluster.text.end);
+                self.cluster_x(cluster, index)
+            }
+            None => line.x,
+        };
+
+        Caret {
+            x,
+            y: line.y,
+            height: line.height,
+        }
+    }
+
+    /// Finds the rectangles covering the text in a byte range, with one rectangle for each
+    /// visually contiguous part of the range on each line.
+    pub fn selection_rects(&self, range: Range<usize>) -> Vec<Rect> {
+        let mut rects = Vec::new();
+        if range.is_empty() {
+            return rects;
+        }
+
+        for line in &self.lines {
+            let first = rects.len();
+            for cluster in &self.clusters[line.clusters.clone()] {
+                let start = cluster.text.start.max(range.start);
+                let end = cluster.text.end.min(range.end);
+                if start >= end {
+                    continue;
+                }
+
+                let x1 = self.cluster_x(cluster, start);
+                let x2 = self.cluster_x(cluster, end);
+                let (left, right) = (x1.min(x2), x1.max(x2));
+                match rects[first..].last_mut() {
+                    Some(rect) if (rect.max.x - left).abs() < 1e-3 => rect.max.x = right,
+                    _ => rects.push(Rect::new(
+                        Point::new(left, line.y),
+                        Point::new(right, line.y + line.height),
+                    )),
+                }
+            }
+        }
+
+        rects
+    }
+
+    /// Finds the last caret position on a line.
+    fn caret_end(&self, index: usize) -> usize {
+        let line = &self.lines[index];
+        let content = &self.text[line.text.clone()];
+        let end = line.text.start + content.trim_end_matches(is_line_break).len();
+
+        if index + 1 < self.lines.len() {
+            // The end of a wrapped line is the start of the next one.
+            if end == line.text.end && end > line.text.start {
+                return self.previous_grapheme(end).max(line.text.start);
+            }
+            end
+        } else if line.text.end < self.text.len() {
+            // The text was truncated, and the rest of it is represented by the ellipsis.
+            self.text.len()
+        } else {
+            end
+        }
+    }
+
+    /// Iterates over the grapheme cluster boundaries within a cluster, including both of its
+    /// ends.
+    fn boundaries<'a>(&'a self, cluster: &'a Cluster) -> impl Iterator<Item = usize> + 'a {
+        let text = &self.text[cluster.text.clone()];
+        let starts = text.grapheme_indices(true).map(|(offset, _)| cluster.text.start + offset);
+        starts.chain([cluster.text.end])
+    }
+
+    /// Finds the horizontal position of a byte offset within a cluster.
+    fn cluster_x(&self, cluster: &Cluster, index: usize) -> f32 {
+        let text = &self.text[cluster.text.clone()];
+        let count = text.graphemes(true).count();
+        let before = self.boundaries(cluster).filter(|&offset| offset < index).count();
+        let fraction = if count == 0 {
+            0.0
+        } else {
+            before.min(count) as f32 / count as f32
+        };
+
+        if cluster.rtl {
+            cluster.x + cluster.width * (1.0 - fraction)
+        } else {
+            cluster.x + cluster.width * fraction
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::paragraph::ParagraphStyle;
+    use crate::text::{Font, TextLayout};
+
+    fn font() -> Font {
+        Font::from_bytes(
+            include_bytes!("../examples/res/SourceSansPro-Regular.otf"),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn layout(text: &str, max_width: f32) -> TextLayout {
+        TextLayout::with_style(
+            text,
+            &font(),
+            16.0,
+            ParagraphStyle::new().max_width(max_width),
+        )
+    }
+
+    #[test]
+    fn clusters() {
+        // The "ff" ligature is a single cluster.
+        let layout = layout("off \u{5D0}\u{5D1}", f32::INFINITY);
+        let clusters: Vec<_> = layout
+            .clusters()
+            .iter()
+            .map(|cluster| (cluster.text.clone(), cluster.glyphs.len(), cluster.rtl))
+            .collect();
+        assert_eq!(
+            clusters,
+            [
+                (0..1, 1, false),
+                (1..3, 1, false),
+                (3..4, 1, false),
+                (6..8, 1, true),
+                (4..6, 1, true),
+            ]
+        );
+
+        for pair in layout.clusters().windows(2) {
+            assert!((pair[0].x + pair[0].width - pair[1].x).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn carets() {
+        let text = "ab cd\nef";
+        let layout = layout(text, f32::INFINITY);
+        let clusters = layout.clusters();
+
+        for index in 0..=text.len() {
+            let caret = layout.caret_position(index);
+            assert_eq!(layout.hit_test_point(caret.x + 0.1, caret.y + 1.0), index);
+        }
+
+        assert_eq!(layout.caret_position(0).x, 0.0);
+        assert_eq!(layout.caret_position(1).x, clusters[1].x);
+        assert_eq!(layout.caret_position(5).x, layout.lines()[0].width);
+        assert_eq!(layout.caret_position(6).y, layout.lines()[1].y);
+
+        // Points outside the text map to the nearest line and its ends.
+        assert_eq!(layout.hit_test_point(-10.0, -10.0), 0);
+        assert_eq!(layout.hit_test_point(1000.0, 1.0), 5);
+        assert_eq!(layout.hit_test_point(1000.0, 1000.0), text.len());
+
+        // Carets within a ligature divide it evenly.
+        let layout = self::layout("off", f32::INFINITY);
+        let ligature = &layout.clusters()[1];
+        let middle = layout.caret_position(2).x;
+        assert!((middle - (ligature.x + 0.5 * ligature.width)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn rtl_carets() {
+        let text = "\u{5D0}\u{5D1} cd";
+        let layout = layout(text, f32::INFINITY);
+
+        // The paragraph runs from right to left, with the Hebrew text on the right.
+        let start = layout.caret_position(0).x;
+        let after_alef = layout.caret_position(2).x;
+        assert_eq!(start, layout.width());
+        assert!(after_alef < start);
+
+        for index in (0..=text.len()).filter(|&index| text.is_char_boundary(index)) {
+            let caret = layout.caret_position(index);
+            let hit = layout.hit_test_point(caret.x, caret.y);
+            assert_eq!(layout.caret_position(hit).x, caret.x);
+        }
+    }
+
+    #[test]
+    fn wrapped_lines() {
+        let text = "hello world";
+        let layout = layout(text, 50.0);
+        assert_eq!(layout.lines().len(), 2);
+
+        // The end of the first line is before its trailing space, and the offset after the space
+        // is on the next line.
+        assert_eq!(layout.line_end(0), 5);
+        assert_eq!(layout.line_start(8), 6);
+        assert_eq!(layout.line_for_index(6), 1);
+        assert_eq!(layout.caret_position(6).x, 0.0);
+        assert_eq!(layout.hit_test_point(1000.0, 1.0), 5);
+        assert_eq!(layout.line_end(6), text.len());
+    }
+
+    #[test]
+    fn graphemes() {
+        let text = "e\u{301}\u{1F1E9}\u{1F1EA}\r\nx";
+        let layout = layout(text, f32::INFINITY);
+        assert_eq!(layout.next_grapheme(0), 3);
+        assert_eq!(layout.next_grapheme(3), 11);
+        assert_eq!(layout.next_grapheme(11), 13);
+        assert_eq!(layout.next_grapheme(text.len()), text.len());
+        assert_eq!(layout.previous_grapheme(13), 11);
+        assert_eq!(layout.previous_grapheme(3), 0);
+        assert_eq!(layout.previous_grapheme(0), 0);
+    }
+
+    #[test]
+    fn selection() {
+        let text = "ab cd\nef";
+        let layout = layout(text, f32::INFINITY);
+        assert!(layout.selection_rects(2..2).is_empty());
+
+        let rects = layout.selection_rects(1..7);
+        assert_eq!(rects.len(), 2);
+        assert_eq!(rects[0].min.x, layout.caret_position(1).x);
+        assert_eq!(rects[0].max.x, layout.caret_position(5).x);
+        assert_eq!(rects[1].min.x, 0.0);
+        assert_eq!(rects[1].max.x, layout.caret_position(7).x);
+        assert_eq!(rects[1].min.y, layout.lines()[1].y);
+
+        // A selection crossing a direction boundary is split into visually contiguous parts.
+        let layout = self::layout("ab \u{5D0}\u{5D1}\u{5D2} cd", f32::INFINITY);
+        assert_eq!(layout.selection_rects(1..5).len(), 2);
+        assert_eq!(layout.selection_rects(3..9).len(), 1);
+    }
+}
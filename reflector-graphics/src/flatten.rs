@@ -1,199 +1,12 @@
+use std::collections::HashMap;
+
+use crate::curve::{Cubic, Curve, Line, Quadratic};
 use crate::geom::{Affine, Point};
-use crate::path::{Path, Verb};
+use crate::path::{LineCap, LineJoin, Path, StrokeStyle, Verb};
 
 const TOLERANCE: f32 = 0.2;
 const MAX_SEGMENTS: usize = 100;
-
-trait Curve {
-    fn transform(&self, transform: Affine) -> Self;
-
-    fn start(&self) -> Point;
-    fn end(&self) -> Point;
-    fn eval(&self, t: f32) -> Point;
-
-    fn tangent(&self, t: f32) -> Point;
-    #[allow(unused)]
-    fn start_tangent(&self) -> Point;
-    #[allow(unused)]
-    fn end_tangent(&self) -> Point;
-
-    fn segments_for_tolerance(&self, tolerance: f32) -> usize;
-}
-
-#[derive(Copy, Clone)]
-struct Line {
-    p0: Point,
-    p1: Point,
-}
-
-impl Curve for Line {
-    #[inline]
-    fn transform(&self, transform: Affine) -> Self {
-        Line {
-            p0: transform * self.p0,
-            p1: transform * self.p1,
-        }
-    }
-
-    #[inline]
-    fn start(&self) -> Point {
-        self.p0
-    }
-
-    #[inline]
-    fn end(&self) -> Point {
-        self.p1
-    }
-
-    #[inline]
-    fn eval(&self, t: f32) -> Point {
-        Point::lerp(t, self.p0, self.p1)
-    }
-
-    #[inline]
-    fn start_tangent(&self) -> Point {
-        self.p1 - self.p0
-    }
-
-    #[inline]
-    fn end_tangent(&self) -> Point {
-        self.p1 - self.p0
-    }
-
-    #[inline]
-    fn tangent(&self, _t: f32) -> Point {
-        self.p1 - self.p0
-    }
-
-    #[inline]
-    fn segments_for_tolerance(&self, _tolerance: f32) -> usize {
-        1
-    }
-}
-
-#[derive(Copy, Clone)]
-struct Quadratic {
-    p0: Point,
-    p1: Point,
-    p2: Point,
-}
-
-impl Curve for Quadratic {
-    #[inline]
-    fn transform(&self, transform: Affine) -> Self {
-        Quadratic {
-            p0: transform * self.p0,
-            p1: transform * self.p1,
-            p2: transform * self.p2,
-        }
-    }
-
-    #[inline]
-    fn start(&self) -> Point {
-        self.p0
-    }
-
-    #[inline]
-    fn end(&self) -> Point {
-        self.p2
-    }
-
-    #[inline]
-    fn eval(&self, t: f32) -> Point {
-        let p01 = Point::lerp(t, self.p0, self.p1);
-        let p12 = Point::lerp(t, self.p1, self.p2);
-        Point::lerp(t, p01, p12)
-    }
-
-    #[inline]
-    fn start_tangent(&self) -> Point {
-        self.p1 - self.p0
-    }
-
-    #[inline]
-    fn end_tangent(&self) -> Point {
-        self.p2 - self.p1
-    }
-
-    #[inline]
-    fn tangent(&self, t: f32) -> Point {
-        Point::lerp(t, self.p1 - self.p0, self.p2 - self.p1)
-    }
-
-    #[inline]
-    fn segments_for_tolerance(&self, tolerance: f32) -> usize {
-        let dt = ((4.0 * tolerance) / (self.p0 - 2.0 * self.p1 + self.p2).length()).sqrt();
-
-        dt.recip().ceil() as usize
-    }
-}
-
-#[derive(Copy, Clone)]
-struct Cubic {
-    p0: Point,
-    p1: Point,
-    p2: Point,
-    p3: Point,
-}
-
-impl Curve for Cubic {
-    #[inline]
-    fn transform(&self, transform: Affine) -> Self {
-        Cubic {
-            p0: transform * self.p0,
-            p1: transform * self.p1,
-            p2: transform * self.p2,
-            p3: transform * self.p3,
-        }
-    }
-
-    #[inline]
-    fn start(&self) -> Point {
-        self.p0
-    }
-
-    #[inline]
-    fn end(&self) -> Point {
-        self.p3
-    }
-
-    #[inline]
-    fn eval(&self, t: f32) -> Point {
-        let p01 = Point::lerp(t, self.p0, self.p1);
-        let p12 = Point::lerp(t, self.p1, self.p2);
-        let p23 = Point::lerp(t, self.p2, self.p3);
-        let p012 = Point::lerp(t, p01, p12);
-        let p123 = Point::lerp(t, p12, p23);
-        Point::lerp(t, p012, p123)
-    }
-
-    #[inline]
-    fn tangent(&self, t: f32) -> Point {
-        let t1 = Point::lerp(t, self.p1 - self.p0, self.p2 - self.p1);
-        let t2 = Point::lerp(t, self.p2 - self.p1, self.p3 - self.p2);
-        Point::lerp(t, t1, t2)
-    }
-
-    #[inline]
-    fn start_tangent(&self) -> Point {
-        self.p1 - self.p0
-    }
-
-    #[inline]
-    fn end_tangent(&self) -> Point {
-        self.p3 - self.p2
-    }
-
-    #[inline]
-    fn segments_for_tolerance(&self, tolerance: f32) -> usize {
-        let a = -1.0 * self.p0 + 3.0 * self.p1 - 3.0 * self.p2 + self.p3;
-        let b = 3.0 * (self.p0 - 2.0 * self.p1 + self.p2);
-        let conc = b.length().max((a + b).length());
-        let dt = ((8.0f32.sqrt() * tolerance) / conc).sqrt();
-
-        dt.recip().ceil() as usize
-    }
-}
+const MAX_DASHES: usize = 1 << 16;
 
 #[inline]
 fn flatten_curve<C: Curve>(curve: &C, transform: Affine, sink: &mut impl FnMut(Point, Point)) {
@@ -283,128 +96,581 @@ pub fn flatten(path: &Path, transform: Affine, sink: &mut impl FnMut(Point, Poin
     }
 }
 
-struct Stroker<S> {
-    width: f32,
+/// Finds the unit normal of a tangent vector, pointing to the right of the direction of travel
+/// (in a y-down coordinate system).
+#[inline]
+fn unit_normal(tangent: Point) -> Option<Point> {
+    let len = tangent.length();
+    if len < 1e-6 {
+        return None;
+    }
+
+    Some((1.0 / len) * Point::new(-tangent.y, tangent.x))
+}
+
+/// Finds the direction of travel for a unit normal produced by `unit_normal`.
+#[inline]
+fn normal_direction(normal: Point) -> Point {
+    Point::new(normal.y, -normal.x)
+}
+
+/// Finds an upper bound on how much the transform can scale a distance.
+#[inline]
+fn max_scale(transform: Affine) -> f32 {
+    let linear = transform.linear();
+    (linear * Point::new(1.0, 0.0))
+        .length()
+        .max((linear * Point::new(0.0, 1.0)).length())
+}
+
+#[inline]
+fn rotate(vector: Point, angle: f32) -> Point {
+    let (sin, cos) = angle.sin_cos();
+    Point::new(
+        vector.x * cos - vector.y * sin,
+        vector.x * sin + vector.y * cos,
+    )
+}
+
+// The stroker works in the untransformed coordinate space of the path, so that the stroke is
+// the image of a circular pen under the transform, and transforms its output before passing it
+// to the sink. The right side of the stroke is emitted in the direction of travel and the left
+// side in the opposite direction, so that together with joins and caps each subpath produces a
+// closed outline with a consistent nonzero winding.
+struct Stroker<'a, S> {
+    style: &'a StrokeStyle,
+    half_width: f32,
     transform: Affine,
-    first_right: Point,
-    first_left: Point,
-    prev_right: Point,
-    prev_left: Point,
+    device_tolerance: f32,
+    tolerance: f32,
+    round_step: f32,
+    first_point: Point,
+    first_normal: Point,
+    prev_point: Point,
+    prev_normal: Point,
     closed: bool,
+    dot: Option<Point>,
     sink: S,
 }
 
-impl<S: FnMut(Point, Point)> Stroker<S> {
+impl<'a, S: FnMut(Point, Point)> Stroker<'a, S> {
     #[inline]
-    fn new(width: f32, transform: Affine, sink: S) -> Stroker<S> {
+    fn new(
+        style: &'a StrokeStyle,
+        transform: Affine,
+        device_tolerance: f32,
+        sink: S,
+    ) -> Stroker<'a, S> {
+        let half_width = 0.5 * style.width;
+        let tolerance = device_tolerance / max_scale(transform);
+        let round_step = if tolerance < half_width {
+            2.0 * (1.0 - tolerance / half_width).acos()
+        } else {
+            std::f32::consts::FRAC_PI_2
+        };
+
         Stroker {
-            width,
+            style,
+            half_width,
             transform,
-            first_right: Point::new(0.0, 0.0),
-            first_left: Point::new(0.0, 0.0),
-            prev_right: Point::new(0.0, 0.0),
-            prev_left: Point::new(0.0, 0.0),
+            device_tolerance,
+            tolerance,
+            round_step,
+            first_point: Point::new(0.0, 0.0),
+            first_normal: Point::new(0.0, 0.0),
+            prev_point: Point::new(0.0, 0.0),
+            prev_normal: Point::new(0.0, 0.0),
             closed: true,
+            dot: None,
             sink,
         }
     }
 
     #[inline]
-    fn cap_begin(&mut self) {
-        (self.sink)(self.first_left, self.first_right);
+    fn line(&mut self, p1: Point, p2: Point) {
+        (self.sink)(self.transform * p1, self.transform * p2);
     }
 
     #[inline]
-    fn cap_end(&mut self) {
-        (self.sink)(self.prev_right, self.prev_left);
+    fn edge(&mut self, p1: Point, p2: Point, reverse: bool) {
+        if reverse {
+            self.line(p2, p1);
+        } else {
+            self.line(p1, p2);
+        }
     }
 
+    /// Emits a circular arc around `center` from `center + radius * from`, sweeping through
+    /// `angle` radians, and returns its end point.
     #[inline]
-    fn join(&mut self, right: Point, left: Point) {
-        (self.sink)(self.prev_right, right);
-        (self.sink)(left, self.prev_left);
+    fn arc(&mut self, center: Point, from: Point, angle: f32, reverse: bool) -> Point {
+        let steps = (angle.abs() / self.round_step).ceil().max(1.0) as usize;
+        let step = angle / steps as f32;
+
+        let mut prev = center + self.half_width * from;
+        for i in 1..=steps {
+            let point = center + self.half_width * rotate(from, step * i as f32);
+            self.edge(prev, point, reverse);
+            prev = point;
+        }
+
+        prev
     }
 
+    /// Emits a cap at `point`, running from the side given by `normal` to the opposite side and
+    /// extending in the direction `direction`.
     #[inline]
-    fn stroke_curve<C: Curve>(&mut self, curve: &C) {
-        if curve.start() == curve.end() {
+    fn cap(&mut self, point: Point, normal: Point, direction: Point) {
+        let from = point + self.half_width * normal;
+        let to = point - self.half_width * normal;
+
+        match self.style.cap {
+            LineCap::Butt => {
+                self.line(from, to);
+            }
+            LineCap::Round => {
+                let angle = if normal.cross(direction) > 0.0 {
+                    std::f32::consts::PI
+                } else {
+                    -std::f32::consts::PI
+                };
+                let end = self.arc(point, normal, angle, false);
+                self.line(end, to);
+            }
+            LineCap::Square => {
+                let extend = self.half_width * direction;
+                self.line(from, from + extend);
+                self.line(from + extend, to + extend);
+                self.line(to + extend, to);
+            }
+        }
+    }
+
+    #[inline]
+    fn join(&mut self, point: Point, normal: Point) {
+        let prev_normal = self.prev_normal;
+        let cross = prev_normal.cross(normal);
+        let dot = prev_normal.dot(normal);
+
+        let prev_right = point + self.half_width * prev_normal;
+        let prev_left = point - self.half_width * prev_normal;
+        let right = point + self.half_width * normal;
+        let left = point - self.half_width * normal;
+
+        // Nearly collinear segments can be connected directly.
+        if cross.abs() < 1e-3 && dot > 0.0 {
+            self.line(prev_right, right);
+            self.line(left, prev_left);
             return;
         }
 
-        let curve_transformed = curve.transform(self.transform);
+        // The inner side is routed through the center point, and the outer side gets the join.
+        let (outer_from, outer_to, reverse) = if cross > 0.0 {
+            self.line(prev_right, point);
+            self.line(point, right);
+            (-prev_normal, -normal, true)
+        } else {
+            self.line(left, point);
+            self.line(point, prev_left);
+            (prev_normal, normal, false)
+        };
+
+        let from = point + self.half_width * outer_from;
+        let to = point + self.half_width * outer_to;
+
+        match self.style.join {
+            LineJoin::Miter => {
+                // The ratio of miter length to stroke width is 1 / cos(θ / 2), where θ is the
+                // angle between the normals, and 2 cos²(θ / 2) = 1 + cos θ.
+                let denom = 1.0 + dot;
+                let limit = self.style.miter_limit;
+                if denom * limit * limit >= 2.0 {
+                    let miter = point + (self.half_width / denom) * (outer_from + outer_to);
+                    self.edge(from, miter, reverse);
+                    self.edge(miter, to, reverse);
+                } else {
+                    self.edge(from, to, reverse);
+                }
+            }
+            LineJoin::Round => {
+                let angle = outer_from.cross(outer_to).atan2(outer_from.dot(outer_to));
+                let end = self.arc(point, outer_from, angle, reverse);
+                self.edge(end, to, reverse);
+            }
+            LineJoin::Bevel => {
+                self.edge(from, to, reverse);
+            }
+        }
+    }
+
+    #[inline]
+    fn normal_at<C: Curve>(curve: &C, t: f32, dt: f32) -> Option<Point> {
+        unit_normal(curve.tangent(t))
+            .or_else(|| unit_normal(curve.tangent(t.clamp(dt.min(0.5), 1.0 - dt.min(0.5)))))
+            .or_else(|| unit_normal(curve.end() - curve.start()))
+    }
+
+    #[inline]
+    fn stroke_curve<C: Curve>(&mut self, curve: &C) {
+        if curve.is_point() {
+            if self.closed {
+                self.dot = Some(curve.start());
+            }
+            return;
+        }
 
-        let segments = curve_transformed.segments_for_tolerance(TOLERANCE).clamp(1, MAX_SEGMENTS);
+        let segments = curve
+            .transform(self.transform)
+            .segments_for_tolerance(self.device_tolerance)
+            .clamp(1, MAX_SEGMENTS);
         let dt = 1.0 / segments as f32;
 
-        let start = curve_transformed.start();
-        let start_tangent = curve.tangent(dt.min(0.5));
-        let start_normal = Point::new(-start_tangent.y, start_tangent.x);
-        let normal_len = start_normal.length();
-        let offset = if normal_len.abs() < 1e-6 {
-            Point::new(0.0, 0.0)
-        } else {
-            0.5 * self.width * (1.0 / normal_len) * start_normal
+        let start = curve.start();
+        let Some(normal) = Self::normal_at(curve, 0.0, dt) else {
+            return;
         };
-        let offset_transformed = self.transform.linear() * offset;
-        let right = start + offset_transformed;
-        let left = start - offset_transformed;
 
         if self.closed {
-            self.first_right = right;
-            self.first_left = left;
+            self.first_point = start;
+            self.first_normal = normal;
             self.closed = false;
         } else {
-            self.join(right, left);
+            self.join(start, normal);
         }
 
-        self.prev_right = right;
-        self.prev_left = left;
+        self.prev_point = start;
+        self.prev_normal = normal;
 
         let mut t = dt;
-        for _ in 0..segments {
-            let point = curve_transformed.eval(t);
-            let tangent = curve.tangent(t.min(1.0 - dt));
-            let normal = Point::new(-tangent.y, tangent.x);
-            let normal_len = normal.length();
-            let offset = if normal_len.abs() < 1e-6 {
-                Point::new(0.0, 0.0)
+        for i in 0..segments {
+            let point = if i == segments - 1 {
+                curve.end()
             } else {
-                0.5 * self.width * (1.0 / normal_len) * normal
+                curve.eval(t)
             };
-            let offset_transformed = self.transform.linear() * offset;
-            let right = point + offset_transformed;
-            let left = point - offset_transformed;
+            let normal = Self::normal_at(curve, t.min(1.0), dt).unwrap_or(self.prev_normal);
 
-            (self.sink)(self.prev_right, right);
-            (self.sink)(left, self.prev_left);
+            let prev_right = self.prev_point + self.half_width * self.prev_normal;
+            let prev_left = self.prev_point - self.half_width * self.prev_normal;
+            self.line(prev_right, point + self.half_width * normal);
+            self.line(point - self.half_width * normal, prev_left);
 
-            self.prev_right = right;
-            self.prev_left = left;
+            self.prev_point = point;
+            self.prev_normal = normal;
             t += dt;
         }
-
-        self.closed = false;
     }
 
     #[inline]
     fn close(&mut self) {
-        self.join(self.first_right, self.first_left);
+        if self.closed {
+            self.finish();
+            return;
+        }
+
+        self.join(self.first_point, self.first_normal);
         self.closed = true;
+        self.dot = None;
     }
 
     #[inline]
     fn finish(&mut self) {
         if !self.closed {
-            self.cap_begin();
-            self.cap_end();
+            let start_direction = normal_direction(self.first_normal);
+            self.cap(self.first_point, -self.first_normal, -start_direction);
+
+            let end_direction = normal_direction(self.prev_normal);
+            self.cap(self.prev_point, self.prev_normal, end_direction);
+
             self.closed = true;
+        } else if let Some(point) = self.dot {
+            // Zero-length subpaths are drawn as a pair of caps facing in opposite directions.
+            if self.style.cap != LineCap::Butt {
+                let normal = Point::new(0.0, 1.0);
+                let direction = normal_direction(normal);
+                self.cap(point, -normal, -direction);
+                self.cap(point, normal, direction);
+            }
         }
+
+        self.dot = None;
     }
 }
 
+/// Flattens a curve without transforming it, choosing the number of segments based on the size of
+/// the curve after transformation.
 #[inline]
-pub fn stroke(path: &Path, width: f32, transform: Affine, sink: &mut impl FnMut(Point, Point)) {
-    let mut stroker = Stroker::new(width, transform, sink);
+fn flatten_untransformed<C: Curve>(
+    curve: &C,
+    transform: Affine,
+    tolerance: f32,
+    points: &mut Vec<Point>,
+) {
+    let segments = curve
+        .transform(transform)
+        .segments_for_tolerance(tolerance)
+        .clamp(1, MAX_SEGMENTS);
+    let dt = 1.0 / segments as f32;
+
+    let mut t = dt;
+    for _ in 1..segments {
+        points.push(curve.eval(t));
+        t += dt;
+    }
+    points.push(curve.end());
+}
+
+// Splits flattened subpaths into dashes, each of which becomes a separate open subpath in the
+// output. Dash lengths are measured in the untransformed coordinate space of the path.
+//
+// A pattern which is too fine for a segment would split it into an unbounded number of dashes,
+// and dashes below the precision of the segment length would never advance along it. In either
+// case, `overflow` is set and the path is stroked solid instead.
+struct Dasher<'a> {
+    dashes: &'a [f32],
+    count: usize,
+    period: f32,
+    start_index: usize,
+    start_remaining: f32,
+    first: Vec<Point>,
+    output: Path,
+    overflow: bool,
+}
+
+impl<'a> Dasher<'a> {
+    #[inline]
+    fn new(dashes: &'a [f32], offset: f32) -> Option<Dasher<'a>> {
+        if dashes.iter().any(|&dash| !(dash >= 0.0 && dash.is_finite())) {
+            return None;
+        }
+
+        let count = if dashes.len() % 2 == 1 {
+            2 * dashes.len()
+        } else {
+            dashes.len()
+        };
+
+        let total = dashes.iter().sum::<f32>() * (count / dashes.len().max(1)) as f32;
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut offset = offset.rem_euclid(total);
+        let mut index = 0;
+        while offset > dashes[index % dashes.len()] {
+            offset -= dashes[index % dashes.len()];
+            index = (index + 1) % count;
+        }
+
+        Some(Dasher {
+            dashes,
+            count,
+            period: total,
+            start_index: index,
+            start_remaining: dashes[index % dashes.len()] - offset,
+            first: Vec::new(),
+            output: Path::new(),
+            overflow: false,
+        })
+    }
+
+    fn dash_subpath(&mut self, points: &[Point], closed: bool) {
+        if points.len() < 2 || self.overflow {
+            return;
+        }
+
+        let mut index = self.start_index;
+        let mut remaining = self.start_remaining;
+        let mut on = index.is_multiple_of(2);
+
+        // For closed subpaths, the first dash is held back so that it can be joined with the
+        // last one if the pattern is on at the closing point.
+        let mut in_first = closed && on;
+        let mut split = false;
+        self.first.clear();
+
+        if on {
+            self.point(points[0], in_first, true);
+        }
+
+        for segment in points.windows(2) {
+            let (mut p0, p1) = (segment[0], segment[1]);
+            let mut len = p0.distance(p1);
+            if len / self.period * self.count as f32 > MAX_DASHES as f32 {
+                self.overflow = true;
+                return;
+            }
+
+            let mut splits = 0;
+            while len > remaining {
+                splits += 1;
+                if splits > MAX_DASHES {
+                    self.overflow = true;
+                    return;
+                }
+
+                let point = Point::lerp(remaining / len, p0, p1);
+                self.point(point, in_first, !on);
+                in_first = false;
+                split = true;
+
+                on = !on;
+                len -= remaining;
+                p0 = point;
+                index = (index + 1) % self.count;
+                remaining = self.dashes[index % self.dashes.len()];
+            }
+
+            remaining -= len;
+            if on {
+                self.point(p1, in_first, false);
+            }
+        }
+
+        if !split {
+            if in_first {
+                self.output.move_to(self.first[0]);
+                for &point in &self.first[1..] {
+                    self.output.line_to(point);
+                }
+                self.output.close();
+            }
+            return;
+        }
+
+        if !self.first.is_empty() {
+            if !on {
+                self.output.move_to(self.first[0]);
+            }
+            for &point in &self.first[1..] {
+                self.output.line_to(point);
+            }
+        }
+    }
+
+    #[inline]
+    fn point(&mut self, point: Point, in_first: bool, start: bool) {
+        if in_first {
+            self.first.push(point);
+        } else if start {
+            self.output.move_to(point);
+        } else {
+            self.output.line_to(point);
+        }
+    }
+}
+
+/// Splits a path into dashes according to the dash pattern of a stroke style, or returns `None` if
+/// the style has no valid dash pattern or the pattern is too fine for the path.
+fn dash(path: &Path, style: &StrokeStyle, transform: Affine, tolerance: f32) -> Option<Path> {
+    if style.dashes.is_empty() {
+        return None;
+    }
+
+    let mut dasher = Dasher::new(&style.dashes, style.dash_offset)?;
+
+    let mut subpath = Vec::new();
+    let mut points = path.points.iter();
+    let mut first = Point::new(0.0, 0.0);
+    let mut prev = Point::new(0.0, 0.0);
+    for verb in &path.verbs {
+        match *verb {
+            Verb::Move => {
+                dasher.dash_subpath(&subpath, false);
+                subpath.clear();
+
+                first = *points.next().unwrap();
+                prev = first;
+                subpath.push(first);
+            }
+            Verb::Line => {
+                let p1 = *points.next().unwrap();
+                flatten_untransformed(&Line { p0: prev, p1 }, transform, tolerance, &mut subpath);
+                prev = p1;
+            }
+            Verb::Quadratic => {
+                let p1 = *points.next().unwrap();
+                let p2 = *points.next().unwrap();
+                flatten_untransformed(
+                    &Quadratic { p0: prev, p1, p2 },
+                    transform,
+                    tolerance,
+                    &mut subpath,
+                );
+                prev = p2;
+            }
+            Verb::Cubic => {
+                let p1 = *points.next().unwrap();
+                let p2 = *points.next().unwrap();
+                let p3 = *points.next().unwrap();
+                let cubic = Cubic {
+                    p0: prev,
+                    p1,
+                    p2,
+                    p3,
+                };
+                flatten_untransformed(&cubic, transform, tolerance, &mut subpath);
+                prev = p3;
+            }
+            Verb::Close => {
+                if prev != first {
+                    subpath.push(first);
+                }
+                dasher.dash_subpath(&subpath, true);
+                subpath.clear();
+
+                prev = first;
+                subpath.push(first);
+            }
+        }
+    }
+
+    dasher.dash_subpath(&subpath, false);
+
+    if dasher.overflow {
+        return None;
+    }
+
+    Some(dasher.output)
+}
+
+#[inline]
+pub fn stroke(
+    path: &Path,
+    style: &StrokeStyle,
+    transform: Affine,
+    sink: &mut impl FnMut(Point, Point),
+) {
+    stroke_with_tolerance(path, style, transform, TOLERANCE, sink);
+}
+
+#[inline]
+fn stroke_with_tolerance(
+    path: &Path,
+    style: &StrokeStyle,
+    transform: Affine,
+    tolerance: f32,
+    sink: &mut impl FnMut(Point, Point),
+) {
+    if let Some(dashed) = dash(path, style, transform, tolerance) {
+        stroke_solid(&dashed, style, transform, tolerance, sink);
+    } else {
+        stroke_solid(path, style, transform, tolerance, sink);
+    }
+}
+
+#[inline]
+fn stroke_solid(
+    path: &Path,
+    style: &StrokeStyle,
+    transform: Affine,
+    tolerance: f32,
+    sink: &mut impl FnMut(Point, Point),
+) {
+    let mut stroker = Stroker::new(style, transform, tolerance, sink);
+    if !stroker.tolerance.is_finite() || style.width <= 0.0 {
+        return;
+    }
 
     let mut points = path.points.iter();
     let mut first = Point::new(0.0, 0.0);
@@ -441,12 +707,12 @@ pub fn stroke(path: &Path, width: f32, transform: Affine, sink: &mut impl FnMut(
                 prev = p3;
             }
             Verb::Close => {
-                if prev != first {
-                    stroker.stroke_curve(&Line {
-                        p0: prev,
-                        p1: first,
-                    });
-                }
+                // A degenerate closing segment is still passed on, so that a subpath consisting
+                // of a single point is drawn with caps.
+                stroker.stroke_curve(&Line {
+                    p0: prev,
+                    p1: first,
+                });
                 prev = first;
 
                 stroker.close();
@@ -456,3 +722,156 @@ pub fn stroke(path: &Path, width: f32, transform: Affine, sink: &mut impl FnMut(
 
     stroker.finish();
 }
+
+impl Path {
+    /// Computes the outline of the region covered by stroking the path with the given style, as
+    /// a path which can be filled with [`FillRule::NonZero`](crate::FillRule::NonZero).
+    ///
+    /// Curves are flattened, so that the outline deviates from the exact stroke by at most
+    /// `tolerance`.
+    ///
+    /// The outline may overlap itself, for instance around the inner side of joins. A simple
+    /// outline can be obtained by taking its [`union`](Path::union) with an empty path.
+    pub fn stroke_outline(&self, style: &StrokeStyle, tolerance: f32) -> Path {
+        let mut edges = Vec::new();
+        stroke_with_tolerance(self, style, Affine::id(), tolerance, &mut |p1, p2| {
+            if p1 != p2 {
+                edges.push((p1, p2));
+            }
+        });
+
+        // The stroker emits the edges of each closed outline out of order, but consecutive edges
+        // share exactly equal endpoints, so the outlines can be recovered by chaining them.
+        let key = |point: Point| ((point.x + 0.0).to_bits(), (point.y + 0.0).to_bits());
+        let mut outgoing: HashMap<_, Vec<usize>> = HashMap::new();
+        for (index, &(p1, _)) in edges.iter().enumerate() {
+            outgoing.entry(key(p1)).or_default().push(index);
+        }
+
+        let mut outline = Path::new();
+        let mut used = vec![false; edges.len()];
+        for start in 0..edges.len() {
+            if used[start] {
+                continue;
+            }
+            used[start] = true;
+
+            let (first, mut current) = edges[start];
+            outline.move_to(first);
+            while current != first {
+                outline.line_to(current);
+
+                let next = outgoing
+                    .get(&key(current))
+                    .and_then(|next| next.iter().copied().find(|&next| !used[next]));
+                let Some(next) = next else {
+                    break;
+                };
+                used[next] = true;
+                current = edges[next].1;
+            }
+            outline.close();
+        }
+
+        outline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::FillRule;
+
+    #[test]
+    fn stroke_outline() {
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.line_to(Point::new(10.0, 0.0));
+        path.line_to(Point::new(10.0, 10.0));
+
+        let mut style = StrokeStyle::new(2.0);
+        let outline = path.stroke_outline(&style, 0.1);
+        assert_eq!(
+            outline.verbs.iter().filter(|&&v| v == Verb::Move).count(),
+            1
+        );
+        assert!(outline.contains(Point::new(5.0, 0.9), FillRule::NonZero));
+        assert!(outline.contains(Point::new(10.9, 9.9), FillRule::NonZero));
+        assert!(outline.contains(Point::new(10.9, -0.9), FillRule::NonZero));
+        assert!(!outline.contains(Point::new(5.0, 1.1), FillRule::NonZero));
+        assert!(!outline.contains(Point::new(-0.1, 0.0), FillRule::NonZero));
+        let bounds = outline.bounds().unwrap();
+        assert_eq!(bounds.min, Point::new(0.0, -1.0));
+        assert_eq!(bounds.max, Point::new(11.0, 10.0));
+
+        style.join(LineJoin::Round).cap(LineCap::Round);
+        let outline = path.stroke_outline(&style, 0.01);
+        assert!(outline.contains(Point::new(-0.9, 0.0), FillRule::NonZero));
+        assert!(!outline.contains(Point::new(10.9, -0.9), FillRule::NonZero));
+        assert!((outline.bounds().unwrap().max.y - 11.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn stroke_outline_closed() {
+        let square = Path::rect(Point::new(0.0, 0.0), 10.0, 10.0);
+        let outline = square.stroke_outline(&StrokeStyle::new(2.0), 0.1);
+
+        // A closed subpath produces separate outer and inner outlines.
+        assert_eq!(
+            outline.verbs.iter().filter(|&&v| v == Verb::Move).count(),
+            2
+        );
+        assert!(outline.contains(Point::new(-0.9, 5.0), FillRule::NonZero));
+        assert!(outline.contains(Point::new(0.9, 5.0), FillRule::NonZero));
+        assert!(!outline.contains(Point::new(5.0, 5.0), FillRule::NonZero));
+        assert!(!outline.contains(Point::new(-1.1, 5.0), FillRule::NonZero));
+
+        let mut dashed = StrokeStyle::new(2.0);
+        dashed.dashes(&[5.0, 5.0], 0.0);
+        let outline = square.stroke_outline(&dashed, 0.1);
+        assert_eq!(
+            outline.verbs.iter().filter(|&&v| v == Verb::Move).count(),
+            4
+        );
+        assert!(outline.contains(Point::new(2.5, 0.0), FillRule::NonZero));
+        assert!(!outline.contains(Point::new(7.5, 0.0), FillRule::NonZero));
+    }
+
+    #[test]
+    fn stroke_outline_loop() {
+        // A single curve returning to its start point is stroked rather than treated as a dot.
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.cubic_to(
+            Point::new(100.0, 100.0),
+            Point::new(-100.0, 100.0),
+            Point::new(0.0, 0.0),
+        );
+        path.close();
+
+        let outline = path.stroke_outline(&StrokeStyle::new(2.0), 0.1);
+        assert!(outline.contains(Point::new(0.0, 75.0), FillRule::NonZero));
+        assert!(outline.contains(Point::new(0.0, 75.9), FillRule::NonZero));
+        assert!(!outline.contains(Point::new(0.0, 40.0), FillRule::NonZero));
+        assert!(!outline.contains(Point::new(0.0, 77.0), FillRule::NonZero));
+    }
+
+    #[test]
+    fn stroke_outline_tiny_dashes() {
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.line_to(Point::new(100.0, 0.0));
+
+        // A pattern too fine to resolve along the line is stroked solid.
+        let mut style = StrokeStyle::new(2.0);
+        style.dashes(&[1e-10, 1e-10], 0.0);
+        let outline = path.stroke_outline(&style, 0.1);
+        assert_eq!(
+            outline.verbs.iter().filter(|&&v| v == Verb::Move).count(),
+            1
+        );
+        for x in [0.5, 10.0, 50.0, 99.5] {
+            assert!(outline.contains(Point::new(x, 0.0), FillRule::NonZero));
+        }
+    }
+}
@@ -0,0 +1,663 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rustybuzz::ttf_parser::{self, name_id};
+
+use crate::text::{Font, FontError};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+/// Font weights, as used in the OpenType `OS/2` table and in CSS.
+pub mod weight {
+    pub const THIN: u16 = 100;
+    pub const EXTRA_LIGHT: u16 = 200;
+    pub const LIGHT: u16 = 300;
+    pub const NORMAL: u16 = 400;
+    pub const MEDIUM: u16 = 500;
+    pub const SEMI_BOLD: u16 = 600;
+    pub const BOLD: u16 = 700;
+    pub const EXTRA_BOLD: u16 = 800;
+    pub const BLACK: u16 = 900;
+}
+
+/// Font widths, as used in the OpenType `OS/2` table.
+pub mod width {
+    pub const ULTRA_CONDENSED: u16 = 1;
+    pub const EXTRA_CONDENSED: u16 = 2;
+    pub const CONDENSED: u16 = 3;
+    pub const SEMI_CONDENSED: u16 = 4;
+    pub const NORMAL: u16 = 5;
+    pub const SEMI_EXPANDED: u16 = 6;
+    pub const EXPANDED: u16 = 7;
+    pub const EXTRA_EXPANDED: u16 = 8;
+    pub const ULTRA_EXPANDED: u16 = 9;
+}
+
+const WEIGHT_NAMES: &[(&str, u16)] = &[
+    ("thin", weight::THIN),
+    ("hairline", weight::THIN),
+    ("extralight", weight::EXTRA_LIGHT),
+    ("ultralight", weight::EXTRA_LIGHT),
+    ("light", weight::LIGHT),
+    ("normal", weight::NORMAL),
+    ("regular", weight::NORMAL),
+    ("book", weight::NORMAL),
+    ("medium", weight::MEDIUM),
+    ("semibold", weight::SEMI_BOLD),
+    ("demibold", weight::SEMI_BOLD),
+    ("bold", weight::BOLD),
+    ("extrabold", weight::EXTRA_BOLD),
+    ("ultrabold", weight::EXTRA_BOLD),
+    ("black", weight::BLACK),
+    ("heavy", weight::BLACK),
+];
+
+const WIDTH_NAMES: &[(&str, u16)] = &[
+    ("ultracondensed", width::ULTRA_CONDENSED),
+    ("extracondensed", width::EXTRA_CONDENSED),
+    ("condensed", width::CONDENSED),
+    ("semicondensed", width::SEMI_CONDENSED),
+    ("semiexpanded", width::SEMI_EXPANDED),
+    ("expanded", width::EXPANDED),
+    ("extraexpanded", width::EXTRA_EXPANDED),
+    ("ultraexpanded", width::ULTRA_EXPANDED),
+];
+
+/// The families tried for the generic family names `sans-serif`, `serif` and `monospace` when
+/// no alias has been set for them.
+const DEFAULT_ALIASES: &[(&str, &[&str])] = &[
+    (
+        "sans-serif",
+        &[
+            "DejaVu Sans",
+            "Noto Sans",
+            "Liberation Sans",
+            "Cantarell",
+            "Ubuntu",
+            "Roboto",
+            "FreeSans",
+        ],
+    ),
+    (
+        "serif",
+        &[
+            "DejaVu Serif",
+            "Noto Serif",
+            "Liberation Serif",
+            "FreeSerif",
+        ],
+    ),
+    (
+        "monospace",
+        &[
+            "DejaVu Sans Mono",
+            "Noto Sans Mono",
+            "Liberation Mono",
+            "Ubuntu Mono",
+            "FreeMono",
+        ],
+    ),
+];
+
+/// Lowercases a name and removes spaces, hyphens and underscores, so that e.g. "Semi Bold",
+/// "semi-bold" and "SemiBold" compare equal.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Maps the various spellings of the generic family names to their canonical names.
+fn generic_family(name: &str) -> Option<&'static str> {
+    match normalize(name).as_str() {
+        "sans" | "sansserif" => Some("sans-serif"),
+        "serif" => Some("serif"),
+        "mono" | "monospace" => Some("monospace"),
+        _ => None,
+    }
+}
+
+/// Decodes a Unicode (UTF-16BE) entry of the `name` table.
+fn decode_name(name: &ttf_parser::name::Name) -> Option<String> {
+    if !name.is_unicode() {
+        return None;
+    }
+
+    let units = name.name.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]]));
+    char::decode_utf16(units).collect::<Result<_, _>>().ok()
+}
+
+/// A description of a font to find in a [`FontDatabase`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FontQuery {
+    /// Family names, in order of preference. The generic families `sans-serif`, `serif` and
+    /// `monospace` resolve to the families set with [`FontDatabase::set_alias`].
+    pub families: Vec<String>,
+    pub weight: u16,
+    pub width: u16,
+    pub style: FontStyle,
+}
+
+impl FontQuery {
+    /// Constructs a query for a family with normal weight, width and style.
+    #[inline]
+    pub fn new(family: &str) -> FontQuery {
+        FontQuery {
+            families: vec![family.to_owned()],
+            weight: weight::NORMAL,
+            width: width::NORMAL,
+            style: FontStyle::Normal,
+        }
+    }
+
+    /// Parses a comma-separated description such as `"Sans, Bold, Italic"`.
+    ///
+    /// Weight names (such as `Light` or `SemiBold`), width names (such as `Condensed`), the style
+    /// names `Italic` and `Oblique`, and `Normal` or `Regular` set the corresponding properties.
+    /// Any other item is a family name, with earlier families preferred over later ones.
+    pub fn parse(description: &str) -> FontQuery {
+        let mut query = FontQuery {
+            families: Vec::new(),
+            ..FontQuery::new("")
+        };
+
+        for item in description.split(',') {
+            let item = item.trim();
+            let name = normalize(item);
+            if item.is_empty() {
+                continue;
+            } else if let Some(&(_, weight)) = WEIGHT_NAMES.iter().find(|(n, _)| *n == name) {
+                query.weight = weight;
+            } else if let Some(&(_, width)) = WIDTH_NAMES.iter().find(|(n, _)| *n == name) {
+                query.width = width;
+            } else if name == "italic" {
+                query.style = FontStyle::Italic;
+            } else if name == "oblique" {
+                query.style = FontStyle::Oblique;
+            } else {
+                query.families.push(item.to_owned());
+            }
+        }
+
+        query
+    }
+
+    #[inline]
+    pub fn family(&mut self, family: &str) -> &mut Self {
+        self.families.push(family.to_owned());
+        self
+    }
+
+    #[inline]
+    pub fn weight(&mut self, weight: u16) -> &mut Self {
+        self.weight = weight;
+        self
+    }
+
+    #[inline]
+    pub fn width(&mut self, width: u16) -> &mut Self {
+        self.width = width;
+        self
+    }
+
+    #[inline]
+    pub fn style(&mut self, style: FontStyle) -> &mut Self {
+        self.style = style;
+        self
+    }
+}
+
+/// A face found in a font file.
+#[derive(Clone, Debug)]
+pub struct FaceInfo {
+    pub path: PathBuf,
+    /// The index of the face within its file.
+    pub index: usize,
+    /// The family names of the face, with the typographic family name first if there is one.
+    pub families: Vec<String>,
+    pub weight: u16,
+    pub width: u16,
+    pub style: FontStyle,
+    pub monospaced: bool,
+}
+
+impl FaceInfo {
+    fn parse(path: &Path, data: &[u8], index: usize) -> Option<FaceInfo> {
+        let face = ttf_parser::Face::parse(data, index as u32).ok()?;
+
+        // Prefer English names, but accept any name if there are none.
+        let mut families = Vec::new();
+        for id in [name_id::TYPOGRAPHIC_FAMILY, name_id::FAMILY] {
+            let names = face.names().into_iter().filter(|name| name.name_id == id);
+            let english = names.clone().filter(|name| name.language_id == 0x0409);
+            for name in english.chain(names) {
+                if let Some(name) = decode_name(&name) {
+                    if !families.contains(&name) {
+                        families.push(name);
+                    }
+                    break;
+                }
+            }
+        }
+        if families.is_empty() {
+            return None;
+        }
+
+        Some(FaceInfo {
+            path: path.to_owned(),
+            index,
+            families,
+            weight: face.weight().to_number(),
+            width: face.width().to_number(),
+            style: match face.style() {
+                ttf_parser::Style::Normal => FontStyle::Normal,
+                ttf_parser::Style::Italic => FontStyle::Italic,
+                ttf_parser::Style::Oblique => FontStyle::Oblique,
+            },
+            monospaced: face.is_monospaced(),
+        })
+    }
+
+    /// Loads the face from its file.
+    #[inline]
+    pub fn load(&self) -> Result<Font, FontError> {
+        Font::from_file(&self.path, self.index)
+    }
+}
+
+/// An index of the faces in a set of font files, which can be searched by family, weight, width
+/// and style.
+#[derive(Clone, Default)]
+pub struct FontDatabase {
+    faces: Vec<FaceInfo>,
+    aliases: HashMap<String, Vec<String>>,
+}
+
+impl FontDatabase {
+    /// Constructs an empty font database.
+    #[inline]
+    pub fn new() -> FontDatabase {
+        FontDatabase {
+            faces: Vec::new(),
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Constructs a font database containing the fonts installed on the system.
+    #[inline]
+    pub fn system() -> FontDatabase {
+        let mut db = FontDatabase::new();
+        db.add_system_fonts();
+        db
+    }
+
+    /// Adds the fonts in the standard font directories: those under `/usr/share/fonts`,
+    /// `/usr/local/share/fonts`, the `fonts` directories of `$XDG_DATA_HOME` and
+    /// `$XDG_DATA_DIRS`, and `~/.fonts`.
+    pub fn add_system_fonts(&mut self) {
+        let mut dirs = Vec::new();
+
+        let data_dirs = env::var("XDG_DATA_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| String::from("/usr/local/share:/usr/share"));
+        for dir in data_dirs.split(':').filter(|dir| !dir.is_empty()) {
+            dirs.push(Path::new(dir).join("fonts"));
+        }
+
+        let home = env::var_os("HOME").map(PathBuf::from);
+        match env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+            Some(dir) => dirs.push(Path::new(&dir).join("fonts")),
+            None => dirs.extend(home.iter().map(|home| home.join(".local/share/fonts"))),
+        }
+        dirs.extend(home.iter().map(|home| home.join(".fonts")));
+
+        for dir in [
+            Path::new("/usr/share/fonts"),
+            Path::new("/usr/local/share/fonts"),
+        ] {
+            if !dirs.iter().any(|d| d == dir) {
+                dirs.push(dir.to_owned());
+            }
+        }
+
+        for dir in dirs {
+            self.add_dir(dir);
+        }
+    }
+
+    /// Adds the font files in a directory and its subdirectories. Files which cannot be read or
+    /// are not fonts are skipped.
+    pub fn add_dir(&mut self, dir: impl AsRef<Path>) {
+        self.add_dir_recursive(dir.as_ref(), &mut HashSet::new());
+    }
+
+    /// Adds the font files in a directory and its subdirectories, skipping directories which
+    /// have already been visited through symbolic links.
+    fn add_dir_recursive(&mut self, dir: &Path, visited: &mut HashSet<PathBuf>) {
+        let Ok(canonical) = fs::canonicalize(dir) else {
+            return;
+        };
+        if !visited.insert(canonical) {
+            return;
+        }
+
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+
+        let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+        paths.sort();
+        for path in paths {
+            if path.is_dir() {
+                self.add_dir_recursive(&path, visited);
+                continue;
+            }
+
+            let extension = path.extension().and_then(|ext| ext.to_str());
+            let is_font = extension.is_some_and(|ext| {
+                matches!(
+                    ext.to_ascii_lowercase().as_str(),
+                    "ttf" | "otf" | "ttc" | "otc"
+                )
+            });
+            if is_font {
+                let _ = self.add_file(&path);
+            }
+        }
+    }
+
+    /// Adds every face in a font file, returning the number of faces added.
+    pub fn add_file(&mut self, path: impl AsRef<Path>) -> Result<usize, FontError> {
+        let path = path.as_ref();
+        if self.faces.iter().any(|face| face.path == path) {
+            return Ok(0);
+        }
+
+        let data = fs::read(path)?;
+
+        let count = Font::face_count(&data);
+        if count == 0 {
+            return Err(FontError::InvalidFont);
+        }
+
+        let start = self.faces.len();
+        for index in 0..count {
+            self.faces.extend(FaceInfo::parse(path, &data, index));
+        }
+
+        Ok(self.faces.len() - start)
+    }
+
+    /// Sets the families, in order of preference, that a family name resolves to. The generic
+    /// family names `sans-serif` (or `sans`), `serif` and `monospace` (or `mono`) are predefined.
+    pub fn set_alias(&mut self, name: &str, families: &[&str]) {
+        let name = generic_family(name).map_or_else(|| normalize(name), String::from);
+        self.aliases.insert(name, families.iter().map(|&f| f.to_owned()).collect());
+    }
+
+    #[inline]
+    pub fn faces(&self) -> &[FaceInfo] {
+        &self.faces
+    }
+
+    /// Finds the face which best matches a query.
+    ///
+    /// Families are tried in order, and the first family with any faces is used. Within it, the
+    /// face is chosen by width, then style, then weight, following the CSS font matching
+    /// algorithm. If no family in the query is found, and none of them is a generic family, the
+    /// `sans-serif` family is used.
+    pub fn query(&self, query: &FontQuery) -> Option<&FaceInfo> {
+        let mut families = Vec::new();
+        for family in &query.families {
+            self.resolve(family, &mut families);
+        }
+        if !query.families.iter().any(|family| generic_family(family).is_some()) {
+            self.resolve("sans-serif", &mut families);
+        }
+
+        for family in families {
+            let family = normalize(&family);
+            let candidates: Vec<_> = self
+                .faces
+                .iter()
+                .filter(|face| face.families.iter().any(|name| normalize(name) == family))
+                .collect();
+            if let Some(face) = best_match(&candidates, query) {
+                return Some(face);
+            }
+        }
+
+        None
+    }
+
+    /// Finds the face which best matches a query and loads it.
+    #[inline]
+    pub fn load(&self, query: &FontQuery) -> Option<Font> {
+        self.query(query)?.load().ok()
+    }
+
+    /// Expands a family name into the families it refers to.
+    fn resolve(&self, family: &str, families: &mut Vec<String>) {
+        let generic = generic_family(family);
+        let alias = generic.map_or_else(|| normalize(family), String::from);
+        if let Some(aliased) = self.aliases.get(alias.as_str()) {
+            families.extend(aliased.iter().cloned());
+        } else if let Some(generic) = generic {
+            let (_, defaults) = DEFAULT_ALIASES.iter().find(|(name, _)| *name == generic).unwrap();
+            families.extend(defaults.iter().map(|&family| family.to_owned()));
+
+            // Fall back to any face with the right characteristics.
+            if generic == "monospace" {
+                let monospaced = self.faces.iter().filter(|face| face.monospaced);
+                families.extend(monospaced.map(|face| face.families[0].clone()));
+            }
+        } else {
+            families.push(family.to_owned());
+        }
+    }
+}
+
+/// Chooses the face among the faces of a family which best matches a query.
+fn best_match<'a>(faces: &[&'a FaceInfo], query: &FontQuery) -> Option<&'a FaceInfo> {
+    // Narrower widths are preferred for normal or narrower queries, and wider widths otherwise.
+    let width_key = |face: &&FaceInfo| {
+        let (width, desired) = (face.width as i32, query.width as i32);
+        let narrower_first = desired <= width::NORMAL as i32;
+        match (width - desired, narrower_first) {
+            (0, _) => 0,
+            (d, true) if d < 0 => -d,
+            (d, true) => 10 + d,
+            (d, false) if d > 0 => d,
+            (d, false) => 10 - d,
+        }
+    };
+    let best_width = faces.iter().map(width_key).min()?;
+    let faces: Vec<_> =
+        faces.iter().copied().filter(|face| width_key(face) == best_width).collect();
+
+    let style_order = match query.style {
+        FontStyle::Normal => [FontStyle::Normal, FontStyle::Oblique, FontStyle::Italic],
+        FontStyle::Italic => [FontStyle::Italic, FontStyle::Oblique, FontStyle::Normal],
+        FontStyle::Oblique => [FontStyle::Oblique, FontStyle::Italic, FontStyle::Normal],
+    };
+    let style_key = |face: &&FaceInfo| style_order.iter().position(|&s| s == face.style).unwrap();
+    let best_style = faces.iter().map(style_key).min()?;
+    let faces: Vec<_> = faces.into_iter().filter(|face| style_key(face) == best_style).collect();
+
+    // Weights between 400 and 500 first look up to 500, then down, then above 500. Lighter
+    // weights look down first and heavier weights look up first.
+    let weight_key = |face: &&FaceInfo| {
+        let (weight, desired) = (face.weight as i32, query.weight as i32);
+        let below = desired - weight + 1000;
+        let above = weight - desired + 2000;
+        if (400..=500).contains(&desired) {
+            if weight >= desired && weight <= 500 {
+                weight - desired
+            } else if weight < desired {
+                below
+            } else {
+                above
+            }
+        } else if desired < 400 {
+            if weight <= desired {
+                desired - weight
+            } else {
+                above
+            }
+        } else if weight >= desired {
+            weight - desired
+        } else {
+            below
+        }
+    };
+    faces.into_iter().min_by_key(weight_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn face(family: &str, weight: u16, width: u16, style: FontStyle) -> FaceInfo {
+        FaceInfo {
+            path: PathBuf::from(format!("{family}-{weight}-{width}-{style:?}.ttf")),
+            index: 0,
+            families: vec![family.to_owned()],
+            weight,
+            width,
+            style,
+            monospaced: family.ends_with("Mono"),
+        }
+    }
+
+    fn database() -> FontDatabase {
+        let mut db = FontDatabase::new();
+        for weight in [300, 400, 700, 900] {
+            db.faces.push(face("Test Sans", weight, width::NORMAL, FontStyle::Normal));
+        }
+        db.faces.push(face("Test Sans", 400, width::NORMAL, FontStyle::Italic));
+        db.faces.push(face("Test Sans", 700, width::CONDENSED, FontStyle::Normal));
+        db.faces.push(face("Test Serif", 400, width::NORMAL, FontStyle::Normal));
+        db.faces.push(face("Test Mono", 400, width::NORMAL, FontStyle::Normal));
+        db
+    }
+
+    fn matched(db: &FontDatabase, description: &str) -> (String, u16, u16, FontStyle) {
+        let face = db.query(&FontQuery::parse(description)).unwrap();
+        (
+            face.families[0].clone(),
+            face.weight,
+            face.width,
+            face.style,
+        )
+    }
+
+    #[test]
+    fn parse() {
+        let query = FontQuery::parse("Test Sans, Noto Sans, Semi-Bold, Italic, Condensed");
+        assert_eq!(query.families, ["Test Sans", "Noto Sans"]);
+        assert_eq!(query.weight, weight::SEMI_BOLD);
+        assert_eq!(query.width, width::CONDENSED);
+        assert_eq!(query.style, FontStyle::Italic);
+
+        assert_eq!(FontQuery::parse("Sans"), FontQuery::new("Sans"));
+    }
+
+    #[test]
+    fn matching() {
+        let mut db = database();
+        let normal = width::NORMAL;
+        let sans = |weight, style| ("Test Sans".to_owned(), weight, normal, style);
+
+        assert_eq!(matched(&db, "test sans"), sans(400, FontStyle::Normal));
+        assert_eq!(
+            matched(&db, "Test Sans, Bold"),
+            sans(700, FontStyle::Normal)
+        );
+        assert_eq!(
+            matched(&db, "Test Sans, Medium"),
+            sans(400, FontStyle::Normal)
+        );
+        assert_eq!(
+            matched(&db, "Test Sans, SemiBold"),
+            sans(700, FontStyle::Normal)
+        );
+        assert_eq!(
+            matched(&db, "Test Sans, Thin"),
+            sans(300, FontStyle::Normal)
+        );
+        assert_eq!(
+            matched(&db, "Test Sans, Bold, Italic"),
+            sans(400, FontStyle::Italic)
+        );
+        assert_eq!(
+            matched(&db, "Test Sans, Condensed"),
+            (
+                "Test Sans".to_owned(),
+                700,
+                width::CONDENSED,
+                FontStyle::Normal
+            )
+        );
+
+        // Missing families fall through to later ones, then to the sans-serif alias.
+        assert_eq!(matched(&db, "Missing, Test Serif").0, "Test Serif");
+        assert!(db.query(&FontQuery::new("Missing")).is_none());
+        db.set_alias("sans", &["Test Sans"]);
+        assert_eq!(matched(&db, "Missing, Bold"), sans(700, FontStyle::Normal));
+        assert_eq!(matched(&db, "Sans-Serif").0, "Test Sans");
+
+        // Without an alias, monospace falls back to any monospaced face.
+        assert_eq!(matched(&db, "Monospace").0, "Test Mono");
+    }
+
+    #[test]
+    fn scan() {
+        let mut db = FontDatabase::new();
+        db.add_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/res"));
+        let face = db
+            .faces()
+            .iter()
+            .find(|face| face.path.ends_with("SourceSansPro-Regular.otf"))
+            .unwrap();
+        assert_eq!(face.families, ["Source Sans Pro"]);
+        assert_eq!(face.weight, weight::NORMAL);
+        assert_eq!(face.style, FontStyle::Normal);
+        assert!(!face.monospaced);
+
+        // Files are only added once.
+        let path = face.path.clone();
+        assert_eq!(db.add_file(path).unwrap(), 0);
+
+        let font = db.load(&FontQuery::parse("Source Sans Pro, Bold")).unwrap();
+        assert!(font.has_glyph('A'));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_loop() {
+        let dir = env::temp_dir().join(format!("reflector-font-db-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("fonts")).unwrap();
+        fs::copy(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/examples/res/TestCBDT.ttf"),
+            dir.join("fonts/test.ttf"),
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("fonts/loop")).unwrap();
+
+        // Each directory is only scanned once, however many links lead to it.
+        let mut db = FontDatabase::new();
+        db.add_dir(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(db.faces().len(), 1);
+    }
+}
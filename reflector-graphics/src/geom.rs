@@ -108,6 +108,18 @@ impl ops::SubAssign for Point {
     }
 }
 
+impl ops::Neg for Point {
+    type Output = Point;
+
+    #[inline]
+    fn neg(self) -> Point {
+        Point {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
 impl ops::Mul<Point> for f32 {
     type Output = Point;
 
@@ -136,6 +148,58 @@ impl ops::MulAssign<f32> for Point {
     }
 }
 
+/// An axis-aligned rectangle, described by its minimum and maximum corners.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rect {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Rect {
+    /// Constructs a rectangle from its minimum and maximum corners.
+    #[inline]
+    pub fn new(min: Point, max: Point) -> Rect {
+        Rect { min, max }
+    }
+
+    #[inline]
+    pub fn width(&self) -> f32 {
+        self.max.x - self.min.x
+    }
+
+    #[inline]
+    pub fn height(&self) -> f32 {
+        self.max.y - self.min.y
+    }
+
+    /// Checks whether a point lies within the rectangle, including its boundary.
+    #[inline]
+    pub fn contains(&self, point: Point) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+    }
+
+    /// Finds the smallest rectangle containing both rectangles.
+    #[inline]
+    pub fn union(self, other: Rect) -> Rect {
+        Rect {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Finds the smallest rectangle containing both the rectangle and a point.
+    #[inline]
+    pub fn include(self, point: Point) -> Rect {
+        Rect {
+            min: self.min.min(point),
+            max: self.max.max(point),
+        }
+    }
+}
+
 /// A 2-dimensional affine transformation.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Affine([f32; 6]);
@@ -191,6 +255,32 @@ impl Affine {
     pub fn linear(&self) -> Affine {
         Affine([self.0[0], self.0[1], 0.0, self.0[3], self.0[4], 0.0])
     }
+
+    /// Computes the determinant of the linear part of the transformation.
+    #[inline]
+    pub fn determinant(&self) -> f32 {
+        self.0[0] * self.0[4] - self.0[1] * self.0[3]
+    }
+
+    /// Computes the inverse transformation, or `None` if the transformation is not invertible.
+    #[inline]
+    pub fn inverse(&self) -> Option<Affine> {
+        let det = self.determinant();
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let [a, b, c, d, e, f] = self.0;
+        Some(Affine([
+            inv_det * e,
+            -inv_det * b,
+            inv_det * (b * f - c * e),
+            -inv_det * d,
+            inv_det * a,
+            inv_det * (c * d - a * f),
+        ]))
+    }
 }
 
 impl ops::Mul<Affine> for Affine {
@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rustybuzz::ttf_parser::{GlyphId, OutlineBuilder};
+
+use crate::color_glyph::GlyphBitmap;
+use crate::flatten::flatten;
+use crate::geom::{Affine, Point};
+use crate::path::{FillRule, Path};
+use crate::raster::{Mask, Rasterizer, Segment};
+use crate::text::Font;
+
+/// The number of subpixel positions per pixel in each direction for which glyph masks are cached.
+pub const SUBPIXEL_STEPS: u8 = 4;
+
+/// The largest pixel size at which glyphs are rendered from cached masks. Larger glyphs are
+/// rasterized from their outlines every time.
+pub const MAX_MASK_SIZE: f32 = 256.0;
+
+/// The number of cached outlines above which the outline cache is cleared.
+const MAX_OUTLINES: usize = 4096;
+
+/// The number of cached masks above which the mask cache is cleared.
+const MAX_MASKS: usize = 4096;
+
+/// The number of cached bitmap glyphs above which the bitmap cache is cleared.
+const MAX_BITMAPS: usize = 256;
+
+struct Builder {
+    path: Path,
+    ascent: f32,
+}
+
+impl OutlineBuilder for Builder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.path.move_to(Point::new(x, self.ascent - y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.path.line_to(Point::new(x, self.ascent - y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.path.quadratic_to(
+            Point::new(x1, self.ascent - y1),
+            Point::new(x, self.ascent - y),
+        );
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.path.cubic_to(
+            Point::new(x1, self.ascent - y1),
+            Point::new(x2, self.ascent - y2),
+            Point::new(x, self.ascent - y),
+        );
+    }
+
+    fn close(&mut self) {
+        self.path.close();
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub font: usize,
+    pub glyph: u16,
+    /// The bit pattern of the size of the glyph in pixels.
+    pub size: u32,
+    pub subpixel_x: u8,
+    pub subpixel_y: u8,
+}
+
+/// A pre-rasterized coverage mask for a glyph, positioned relative to the pixel containing the
+/// glyph origin.
+pub struct GlyphMask {
+    pub left: isize,
+    pub top: isize,
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<f32>,
+}
+
+/// Caches glyph outlines, in font units with the origin at the ascender line and y pointing down,
+/// coverage masks of glyphs rendered at particular sizes and subpixel offsets, and decoded images
+/// of bitmap glyphs.
+pub struct GlyphCache {
+    outlines: HashMap<(usize, u16), Option<Arc<Path>>>,
+    masks: HashMap<GlyphKey, Option<GlyphMask>>,
+    // Keyed by font, glyph and requested pixels per em.
+    bitmaps: HashMap<(usize, u16, u16), Option<Arc<GlyphBitmap>>>,
+    segments: Vec<Segment>,
+}
+
+impl GlyphCache {
+    pub fn new() -> GlyphCache {
+        GlyphCache {
+            outlines: HashMap::new(),
+            masks: HashMap::new(),
+            bitmaps: HashMap::new(),
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.outlines.clear();
+        self.masks.clear();
+        self.bitmaps.clear();
+    }
+
+    /// Gets the outline of a glyph, or `None` if the glyph has no outline.
+    pub fn outline(&mut self, font: &Font, glyph: u16) -> Option<Arc<Path>> {
+        let key = (font.id, glyph);
+        if self.outlines.len() >= MAX_OUTLINES && !self.outlines.contains_key(&key) {
+            self.outlines.clear();
+        }
+
+        self.outlines
+            .entry(key)
+            .or_insert_with(|| {
+                let mut builder = Builder {
+                    path: Path::new(),
+                    ascent: font.face.ascender() as f32,
+                };
+                font.face.outline_glyph(GlyphId(glyph), &mut builder)?;
+
+                Some(Arc::new(builder.path))
+            })
+            .clone()
+    }
+
+    /// Gets the color image of a glyph from the bitmap strike of a font best suited to a size in
+    /// pixels per em, decoding it if necessary. Returns `None` if the font has no color image for
+    /// the glyph.
+    pub fn bitmap(
+        &mut self,
+        font: &Font,
+        glyph: u16,
+        pixels_per_em: u16,
+    ) -> Option<Arc<GlyphBitmap>> {
+        let key = (font.id, glyph, pixels_per_em);
+        if !self.bitmaps.contains_key(&key) {
+            if self.bitmaps.len() >= MAX_BITMAPS {
+                self.bitmaps.clear();
+            }
+
+            let bitmap = font
+                .face
+                .glyph_raster_image(GlyphId(glyph), pixels_per_em)
+                .and_then(GlyphBitmap::decode)
+                .map(Arc::new);
+            self.bitmaps.insert(key, bitmap);
+        }
+
+        self.bitmaps[&key].clone()
+    }
+
+    /// The number of cached coverage masks, including those of glyphs which cover no pixels.
+    #[cfg(test)]
+    pub fn mask_count(&self) -> usize {
+        self.masks.len()
+    }
+
+    /// Gets the coverage mask for a glyph, rasterizing it if necessary. Returns `None` if the glyph
+    /// covers no pixels.
+    pub fn mask(
+        &mut self,
+        key: GlyphKey,
+        font: &Font,
+        rasterizer: &mut Rasterizer,
+    ) -> Option<&GlyphMask> {
+        if !self.masks.contains_key(&key) {
+            if self.masks.len() >= MAX_MASKS {
+                self.masks.clear();
+            }
+
+            let mask = self.rasterize(key, font, rasterizer);
+            self.masks.insert(key, mask);
+        }
+
+        self.masks[&key].as_ref()
+    }
+
+    fn rasterize(
+        &mut self,
+        key: GlyphKey,
+        font: &Font,
+        rasterizer: &mut Rasterizer,
+    ) -> Option<GlyphMask> {
+        let outline = self.outline(font, key.glyph)?;
+
+        let steps = SUBPIXEL_STEPS as f32;
+        let scale = f32::from_bits(key.size) / font.face.units_per_em() as f32;
+        let transform =
+            Affine::translate(key.subpixel_x as f32 / steps, key.subpixel_y as f32 / steps)
+                * Affine::scale(scale);
+
+        let bounds = outline.control_bounds()?;
+        let min = transform * bounds.min;
+        let max = transform * bounds.max;
+        let left = min.x.floor() as isize;
+        let top = min.y.floor() as isize;
+        let width = (max.x.ceil() as isize - left) as usize + 1;
+        let height = (max.y.ceil() as isize - top) as usize + 1;
+
+        let offset = Point::new(left as f32, top as f32);
+        self.segments.clear();
+        flatten(&outline, transform, &mut |p1, p2| {
+            self.segments.push(Segment {
+                p1: p1 - offset,
+                p2: p2 - offset,
+            });
+        });
+
+        let mut data = vec![0.0; width * height];
+        rasterizer.set_size(width, height);
+        rasterizer.add_segments(&self.segments);
+        rasterizer.finish(
+            FillRule::NonZero,
+            &mut Mask {
+                data: &mut data,
+                parent: None,
+                stride: width,
+            },
+        );
+
+        Some(GlyphMask {
+            left,
+            top,
+            width,
+            height,
+            data,
+        })
+    }
+}
+
+/// Splits a device coordinate into a whole pixel and a subpixel offset, rounded to the nearest of
+/// [`SUBPIXEL_STEPS`] positions.
+#[inline]
+pub fn quantize(coord: f32) -> (isize, u8) {
+    let steps = (coord * SUBPIXEL_STEPS as f32).round() as isize;
+    let pixel = steps.div_euclid(SUBPIXEL_STEPS as isize);
+    let subpixel = steps.rem_euclid(SUBPIXEL_STEPS as isize) as u8;
+    (pixel, subpixel)
+}
@@ -0,0 +1,80 @@
+use crate::color::Color;
+
+/// An owned bitmap image.
+///
+/// Pixels are stored in row-major order as premultiplied ARGB values, in the same format as the
+/// framebuffer passed to [`Renderer::canvas`](crate::Renderer::canvas).
+#[derive(Clone)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    data: Vec<u32>,
+}
+
+impl Image {
+    /// Constructs a fully transparent image.
+    #[inline]
+    pub fn new(width: usize, height: usize) -> Image {
+        Image {
+            width,
+            height,
+            data: vec![0; width * height],
+        }
+    }
+
+    /// Constructs an image from premultiplied ARGB pixels.
+    #[inline]
+    pub fn from_data(width: usize, height: usize, data: Vec<u32>) -> Image {
+        assert!(data.len() == width * height);
+
+        Image {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Constructs an image from non-premultiplied RGBA bytes, four per pixel.
+    pub fn from_rgba8(width: usize, height: usize, bytes: &[u8]) -> Image {
+        assert!(bytes.len() == 4 * width * height);
+
+        let data = bytes
+            .chunks_exact(4)
+            .map(|p| premultiply(Color::rgba(p[0], p[1], p[2], p[3])))
+            .collect();
+
+        Image {
+            width,
+            height,
+            data,
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    pub fn data(&self) -> &[u32] {
+        &self.data
+    }
+
+    #[inline]
+    pub fn data_mut(&mut self) -> &mut [u32] {
+        &mut self.data
+    }
+}
+
+/// Converts a color to a premultiplied ARGB pixel value.
+#[inline]
+pub(crate) fn premultiply(color: Color) -> u32 {
+    let a = color.a() as u32;
+    let mul = |c: u8| ((c as u32 * a + 127) / 255) as u8;
+    Color::rgba(mul(color.r()), mul(color.g()), mul(color.b()), a as u8).into()
+}
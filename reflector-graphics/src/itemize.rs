@@ -0,0 +1,226 @@
+use std::ops::Range;
+
+use unicode_bidi::Level;
+use unicode_script::{Script, UnicodeScript};
+
+use crate::text::{TextSpan, TextStyle};
+
+/// A range of text which is shaped as a unit, having a single span, bidi embedding level and
+/// script.
+pub struct Item {
+    pub range: Range<usize>,
+    /// The index of the span containing the item.
+    pub span: usize,
+    pub level: u8,
+}
+
+impl Item {
+    #[inline]
+    pub fn is_rtl(&self) -> bool {
+        self.level % 2 == 1
+    }
+}
+
+/// Splits a range of text into items at changes of span, bidi embedding level or script.
+///
+/// Characters of the Common and Inherited scripts, such as spaces, punctuation and combining
+/// marks, take on the script of the surrounding text.
+pub fn itemize(
+    text: &str,
+    range: Range<usize>,
+    spans: &[TextSpan],
+    levels: &[Level],
+    items: &mut Vec<Item>,
+) {
+    let mut span = spans.partition_point(|span| span.range.end <= range.start);
+    let mut current: Option<(Item, Script)> = None;
+    for (offset, c) in text[range.clone()].char_indices() {
+        let offset = range.start + offset;
+        let end = offset + c.len_utf8();
+        while spans[span].range.end <= offset {
+            span += 1;
+        }
+        let level = levels[offset].number();
+        let script = match c.script() {
+            Script::Common | Script::Inherited | Script::Unknown => None,
+            script => Some(script),
+        };
+
+        if let Some((item, item_script)) = &mut current {
+            let compatible = match (script, *item_script) {
+                (Some(script), Script::Common) => {
+                    *item_script = script;
+                    true
+                }
+                (Some(script), item_script) => script == item_script,
+                (None, _) => true,
+            };
+            if item.span == span && item.level == level && compatible {
+                item.range.end = end;
+                continue;
+            }
+        }
+
+        let item = Item {
+            range: offset..end,
+            span,
+            level,
+        };
+        if let Some((item, _)) = current.replace((item, script.unwrap_or(Script::Common))) {
+            items.push(item);
+        }
+    }
+
+    items.extend(current.map(|(item, _)| item));
+}
+
+/// Splits the text of an item into runs of characters which can be displayed with the same font of
+/// a style, identified by its index as in [`TextStyle::font_at`].
+///
+/// Each character uses the first font with a glyph for it, except that characters of the Common
+/// script stay in the font of the preceding text if it supports them, and characters of the
+/// Inherited script, such as combining marks, always stay with the preceding character. Characters
+/// which no font supports use the primary font.
+pub fn split_fonts(
+    text: &str,
+    range: Range<usize>,
+    style: &TextStyle,
+    runs: &mut Vec<(Range<usize>, usize)>,
+) {
+    let font_count = 1 + style.fallback.fonts().len();
+    let first_supporting =
+        |c: char| (0..font_count).find(|&index| style.font_at(index).has_glyph(c));
+
+    let mut current: Option<(Range<usize>, usize)> = None;
+    for (offset, c) in text[range.clone()].char_indices() {
+        let offset = range.start + offset;
+        let end = offset + c.len_utf8();
+
+        let font = match (c.script(), &current) {
+            (Script::Inherited, Some((_, font))) => *font,
+            (Script::Common, Some((_, font))) if style.font_at(*font).has_glyph(c) => *font,
+            _ => first_supporting(c).unwrap_or(0),
+        };
+
+        match &mut current {
+            Some((range, current_font)) if *current_font == font => range.end = end,
+            _ => runs.extend(current.replace((offset..end, font))),
+        }
+    }
+
+    runs.extend(current);
+}
+
+/// Computes the visual order of a line of glyphs from their bidi embedding levels in logical
+/// order, by reversing every maximal sequence at or above each odd level as in rule L2 of the
+/// Unicode Bidirectional Algorithm.
+pub fn visual_order(levels: &[u8]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+    let (Some(&min), Some(&max)) = (levels.iter().min(), levels.iter().max()) else {
+        return order;
+    };
+
+    let mut levels = levels.to_vec();
+    for level in (min | 1..=max).rev() {
+        let mut i = 0;
+        while i < levels.len() {
+            if levels[i] < level {
+                i += 1;
+                continue;
+            }
+
+            let start = i;
+            while i < levels.len() && levels[i] >= level {
+                i += 1;
+            }
+            levels[start..i].reverse();
+            order[start..i].reverse();
+        }
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::{AttributedText, Font, FontCollection, TextStyle};
+    use unicode_bidi::BidiInfo;
+
+    #[test]
+    fn items() {
+        let font = Font::from_bytes(
+            include_bytes!("../examples/res/SourceSansPro-Regular.otf"),
+            0,
+        )
+        .unwrap();
+        let style = TextStyle::new(font, 12.0);
+        let mut text = AttributedText::new("abc שלום, ", &style);
+        text.push("Привет!", &style);
+
+        let bidi = BidiInfo::new(text.text(), None);
+        let mut items = Vec::new();
+        itemize(
+            text.text(),
+            0..text.text().len(),
+            text.spans(),
+            &bidi.levels,
+            &mut items,
+        );
+
+        let items: Vec<_> = items
+            .iter()
+            .map(|item| (&text.text()[item.range.clone()], item.span, item.level))
+            .collect();
+        assert_eq!(
+            items,
+            [
+                ("abc ", 0, 0),
+                ("שלום", 0, 1),
+                (", ", 0, 0),
+                ("Привет!", 1, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn fonts() {
+        // The tiny subset lacks most punctuation and accented letters.
+        let tiny = Font::from_bytes(
+            include_bytes!("../examples/res/SourceSansPro-Regular-Tiny.ttf"),
+            0,
+        )
+        .unwrap();
+        let full = Font::from_bytes(
+            include_bytes!("../examples/res/SourceSansPro-Regular.otf"),
+            0,
+        )
+        .unwrap();
+        let mut style = TextStyle::new(tiny, 12.0);
+        style.fallback(FontCollection::new().push(full));
+
+        let text = "Hello, world! Caf\u{E9}\u{301}s \u{5D0}";
+        let mut runs = Vec::new();
+        split_fonts(text, 0..text.len(), &style, &mut runs);
+        let runs: Vec<_> = runs.iter().map(|(range, font)| (&text[range.clone()], *font)).collect();
+        assert_eq!(
+            runs,
+            [
+                ("Hello", 0),
+                (", ", 1),
+                ("world", 0),
+                ("! ", 1),
+                ("Caf", 0),
+                ("\u{E9}\u{301}", 1),
+                ("s \u{5D0}", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn reorder() {
+        assert_eq!(visual_order(&[0, 0, 1, 1, 1, 0]), [0, 1, 4, 3, 2, 5]);
+        assert_eq!(visual_order(&[1, 1, 2, 2, 1]), [4, 2, 3, 1, 0]);
+        assert_eq!(visual_order(&[]), Vec::<usize>::new());
+    }
+}
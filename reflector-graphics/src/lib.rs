@@ -1,14 +1,33 @@
 mod canvas;
 mod color;
+mod color_glyph;
+mod curve;
+mod editing;
 mod flatten;
+mod font_db;
 mod geom;
+mod glyph_cache;
+mod image;
+mod itemize;
+mod paint;
+mod paragraph;
 mod path;
+mod path_boolean;
+mod path_data;
+mod path_query;
 mod raster;
+mod shader;
 mod simd;
 mod text;
 
 pub use canvas::*;
 pub use color::*;
+pub use editing::Caret;
+pub use font_db::{weight, width, FaceInfo, FontDatabase, FontQuery, FontStyle};
 pub use geom::*;
-pub use path::Path;
+pub use image::Image;
+pub use paint::*;
+pub use paragraph::{Line, ParagraphStyle, TextAlign};
+pub use path::{Command, Commands, FillRule, LineCap, LineJoin, Path, StrokeStyle};
+pub use path_data::PathDataError;
 pub use text::*;
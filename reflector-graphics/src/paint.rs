@@ -0,0 +1,255 @@
+use crate::color::Color;
+use crate::geom::{Affine, Point};
+use crate::image::Image;
+
+/// Determines how a paint is extended outside of its defined area.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Extend {
+    /// Extend the colors at the edges.
+    Pad,
+    /// Repeat the contents.
+    Repeat,
+    /// Repeat the contents, mirroring every other repetition.
+    Reflect,
+}
+
+/// A color stop in a [`Gradient`].
+#[derive(Copy, Clone)]
+pub struct GradientStop {
+    pub offset: f32,
+    pub color: Color,
+}
+
+#[derive(Copy, Clone)]
+pub(crate) enum GradientKind {
+    Linear {
+        start: Point,
+        end: Point,
+    },
+    Radial {
+        center: Point,
+        radius: f32,
+        focus: Point,
+    },
+    Sweep {
+        center: Point,
+        start_angle: f32,
+        end_angle: f32,
+    },
+}
+
+/// A linear, radial, or sweep gradient.
+///
+/// Gradient geometry is specified in gradient space, which is mapped into the coordinate space of
+/// the path being drawn by the gradient's transform.
+#[derive(Clone)]
+pub struct Gradient {
+    pub(crate) kind: GradientKind,
+    pub(crate) stops: Vec<GradientStop>,
+    pub(crate) extend: Extend,
+    pub(crate) transform: Affine,
+}
+
+impl Gradient {
+    #[inline]
+    fn with_kind(kind: GradientKind) -> Gradient {
+        Gradient {
+            kind,
+            stops: Vec::new(),
+            extend: Extend::Pad,
+            transform: Affine::id(),
+        }
+    }
+
+    /// Constructs a linear gradient running from `start` (offset 0) to `end` (offset 1).
+    #[inline]
+    pub fn linear(start: Point, end: Point) -> Gradient {
+        Gradient::with_kind(GradientKind::Linear { start, end })
+    }
+
+    /// Constructs a radial gradient with offset 0 at `center` and offset 1 on the circle of the
+    /// given `radius`.
+    #[inline]
+    pub fn radial(center: Point, radius: f32) -> Gradient {
+        Gradient::with_kind(GradientKind::Radial {
+            center,
+            radius,
+            focus: center,
+        })
+    }
+
+    /// Constructs a radial gradient whose offset 0 lies at the focal point `focus` rather than at
+    /// the center of the circle.
+    ///
+    /// The focal point is moved onto the inside of the circle if it lies outside of it.
+    #[inline]
+    pub fn focal(center: Point, radius: f32, focus: Point) -> Gradient {
+        Gradient::with_kind(GradientKind::Radial {
+            center,
+            radius,
+            focus,
+        })
+    }
+
+    /// Constructs a sweep (conic) gradient around `center`, with offset 0 at `start_angle` and
+    /// offset 1 at `end_angle`.
+    ///
+    /// Angles are in radians and increase from the positive x-axis towards the positive y-axis.
+    #[inline]
+    pub fn sweep(center: Point, start_angle: f32, end_angle: f32) -> Gradient {
+        Gradient::with_kind(GradientKind::Sweep {
+            center,
+            start_angle,
+            end_angle,
+        })
+    }
+
+    /// Adds a color stop. Stops should be added in order of increasing offset.
+    #[inline]
+    pub fn stop(&mut self, offset: f32, color: Color) -> &mut Self {
+        self.stops.push(GradientStop { offset, color });
+        self
+    }
+
+    /// Sets how the gradient is extended outside of the `[0, 1]` offset range.
+    #[inline]
+    pub fn extend(&mut self, extend: Extend) -> &mut Self {
+        self.extend = extend;
+        self
+    }
+
+    /// Sets the transform from gradient space to the coordinate space of the path.
+    #[inline]
+    pub fn transform(&mut self, transform: Affine) -> &mut Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Returns the color stops in the order they were added.
+    #[inline]
+    pub fn stops(&self) -> &[GradientStop] {
+        &self.stops
+    }
+}
+
+/// The filter used when sampling an image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+}
+
+/// A paint which fills an area with an [`Image`].
+///
+/// Image space has its origin at the top-left corner of the image, with one unit per pixel. It is
+/// mapped into the coordinate space of the path being drawn by the pattern's transform. Outside
+/// of the image bounds, [`Extend::Pad`] clamps to the edge pixels.
+#[derive(Copy, Clone)]
+pub struct Pattern<'a> {
+    pub(crate) image: &'a Image,
+    pub(crate) transform: Affine,
+    pub(crate) filter: Filter,
+    pub(crate) extend: Extend,
+}
+
+impl<'a> Pattern<'a> {
+    #[inline]
+    pub fn new(image: &'a Image) -> Pattern<'a> {
+        Pattern {
+            image,
+            transform: Affine::id(),
+            filter: Filter::Bilinear,
+            extend: Extend::Repeat,
+        }
+    }
+
+    #[inline]
+    pub fn filter(&mut self, filter: Filter) -> &mut Self {
+        self.filter = filter;
+        self
+    }
+
+    #[inline]
+    pub fn extend(&mut self, extend: Extend) -> &mut Self {
+        self.extend = extend;
+        self
+    }
+
+    /// Sets the transform from image space to the coordinate space of the path.
+    #[inline]
+    pub fn transform(&mut self, transform: Affine) -> &mut Self {
+        self.transform = transform;
+        self
+    }
+}
+
+/// The source of color for a fill or stroke.
+#[derive(Copy, Clone)]
+pub enum Paint<'a> {
+    Solid(Color),
+    Gradient(&'a Gradient),
+    Pattern(Pattern<'a>),
+}
+
+impl From<Color> for Paint<'static> {
+    #[inline]
+    fn from(color: Color) -> Paint<'static> {
+        Paint::Solid(color)
+    }
+}
+
+impl<'a> From<&'a Gradient> for Paint<'a> {
+    #[inline]
+    fn from(gradient: &'a Gradient) -> Paint<'a> {
+        Paint::Gradient(gradient)
+    }
+}
+
+impl<'a> From<Pattern<'a>> for Paint<'a> {
+    #[inline]
+    fn from(pattern: Pattern<'a>) -> Paint<'a> {
+        Paint::Pattern(pattern)
+    }
+}
+
+impl<'a> From<&Pattern<'a>> for Paint<'a> {
+    #[inline]
+    fn from(pattern: &Pattern<'a>) -> Paint<'a> {
+        Paint::Pattern(*pattern)
+    }
+}
+
+/// Determines how source pixels are combined with the pixels already present in the destination.
+///
+/// The Porter-Duff modes (`Clear` through `Xor`) and `Additive` operate on premultiplied colors.
+/// The separable blend modes (`Multiply` through `Difference`) follow the definitions in the W3C
+/// Compositing and Blending specification and are composited using source-over. Only pixels
+/// covered by the shape being drawn are affected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Clear the destination.
+    Clear,
+    /// Replace the destination with the source.
+    Src,
+    /// Draw the source over the destination.
+    #[default]
+    SrcOver,
+    /// Draw the destination over the source.
+    DstOver,
+    /// Keep the source where the destination is opaque.
+    SrcIn,
+    /// Keep the destination where the source is transparent.
+    DstOut,
+    /// Keep the source and destination where they do not overlap.
+    Xor,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    Difference,
+    /// Add the source to the destination, saturating at full intensity.
+    Additive,
+}
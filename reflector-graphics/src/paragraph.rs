@@ -0,0 +1,938 @@
+use std::iter::zip;
+use std::ops::Range;
+
+use rustybuzz::{ttf_parser, Direction, Feature, UnicodeBuffer};
+use unicode_bidi::BidiInfo;
+use unicode_linebreak::{linebreaks, BreakOpportunity};
+
+use crate::itemize::{itemize, split_fonts, visual_order, Item};
+use crate::text::{AttributedText, Cluster, Glyph, GlyphRun, TextLayout, TextStyle};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TextAlign {
+    /// Aligns lines to the left for left-to-right paragraphs and to the right for right-to-left
+    /// paragraphs.
+    Start,
+    /// Aligns lines to the right for left-to-right paragraphs and to the left for right-to-left
+    /// paragraphs.
+    End,
+    Left,
+    Center,
+    Right,
+    /// Stretches the spaces in each line to fill the width of the layout, except for the last line
+    /// of each paragraph, which is aligned to the start.
+    Justify,
+}
+
+#[derive(Clone, Debug)]
+pub struct ParagraphStyle {
+    /// The width at which lines are wrapped. Lines are never wrapped if this is infinite.
+    pub max_width: f32,
+    pub align: TextAlign,
+    /// The height of each line, as a multiple of the default line height of the font.
+    pub line_height: f32,
+    /// The maximum number of lines. Text past the last line is replaced by [`ellipsis`].
+    ///
+    /// [`ellipsis`]: ParagraphStyle::ellipsis
+    pub max_lines: Option<usize>,
+    /// The string displayed at the end of the last line when text is truncated by `max_lines`.
+    pub ellipsis: String,
+}
+
+impl ParagraphStyle {
+    /// Constructs a paragraph style with no maximum width, start alignment, the default line
+    /// height of the font, and no limit on the number of lines.
+    #[inline]
+    pub fn new() -> ParagraphStyle {
+        ParagraphStyle {
+            max_width: f32::INFINITY,
+            align: TextAlign::Start,
+            line_height: 1.0,
+            max_lines: None,
+            ellipsis: String::from("\u{2026}"),
+        }
+    }
+
+    #[inline]
+    pub fn max_width(&mut self, max_width: f32) -> &mut Self {
+        self.max_width = max_width;
+        self
+    }
+
+    #[inline]
+    pub fn align(&mut self, align: TextAlign) -> &mut Self {
+        self.align = align;
+        self
+    }
+
+    #[inline]
+    pub fn line_height(&mut self, line_height: f32) -> &mut Self {
+        self.line_height = line_height;
+        self
+    }
+
+    #[inline]
+    pub fn max_lines(&mut self, max_lines: Option<usize>) -> &mut Self {
+        self.max_lines = max_lines;
+        self
+    }
+
+    #[inline]
+    pub fn ellipsis(&mut self, ellipsis: &str) -> &mut Self {
+        self.ellipsis.clear();
+        self.ellipsis.push_str(ellipsis);
+        self
+    }
+}
+
+impl Default for ParagraphStyle {
+    #[inline]
+    fn default() -> ParagraphStyle {
+        ParagraphStyle::new()
+    }
+}
+
+/// A line of a [`TextLayout`].
+#[derive(Clone, Debug)]
+pub struct Line {
+    /// The range of [`TextLayout::glyphs`] on this line.
+    pub glyphs: Range<usize>,
+    /// The range of [`TextLayout::clusters`] on this line.
+    pub clusters: Range<usize>,
+    /// The byte range of the source text on this line, including trailing whitespace and line
+    /// breaks.
+    pub text: Range<usize>,
+    /// The horizontal offset of the start of the line, as determined by the alignment.
+    pub x: f32,
+    /// The vertical offset of the top of the line.
+    pub y: f32,
+    /// The width of the line, excluding trailing whitespace.
+    pub width: f32,
+    pub height: f32,
+    /// The vertical offset of the baseline of the line.
+    pub baseline: f32,
+}
+
+#[derive(Copy, Clone)]
+struct ShapedGlyph {
+    id: u16,
+    /// The byte offset in the source text of the cluster this glyph belongs to.
+    cluster: usize,
+    /// The index of the span this glyph belongs to.
+    span: usize,
+    /// The index of the font of the glyph within the fonts of its span's style.
+    font: usize,
+    /// The bidi embedding level of the glyph.
+    level: u8,
+    /// The pen position before the glyph. This only has meaning relative to other glyphs.
+    x: f32,
+    advance: f32,
+    x_offset: f32,
+    y_offset: f32,
+    whitespace: bool,
+}
+
+/// Metrics of the primary font of a text style, in pixels.
+struct SpanMetrics {
+    ascent: f32,
+    descent: f32,
+    /// Half of the space added above and below the font's default line height.
+    half_leading: f32,
+}
+
+impl SpanMetrics {
+    fn new(style: &TextStyle, line_height: f32) -> SpanMetrics {
+        let face = &style.font.face;
+        let scale = style.size / face.units_per_em() as f32;
+        let ascent = scale * face.ascender() as f32;
+        let descent = -scale * face.descender() as f32;
+        SpanMetrics {
+            ascent,
+            descent,
+            half_leading: 0.5 * (line_height - 1.0) * (ascent + descent),
+        }
+    }
+}
+
+/// Shapes the text of an item with the font of `style` with the given index, appending the glyphs
+/// in logical order with pen positions starting at `x`. The text is normally that of `item.range`,
+/// but may be a substitute such as an ellipsis.
+fn shape(
+    text: &str,
+    item: &Item,
+    style: &TextStyle,
+    font: usize,
+    mut x: f32,
+    glyphs: &mut Vec<ShapedGlyph>,
+) {
+    let face = &style.font_at(font).face;
+    let scale = style.size / face.units_per_em() as f32;
+
+    let mut buf = UnicodeBuffer::new();
+    buf.push_str(text);
+    buf.set_direction(if item.is_rtl() {
+        Direction::RightToLeft
+    } else {
+        Direction::LeftToRight
+    });
+    let features: Vec<_> = style
+        .features
+        .iter()
+        .map(|(tag, value)| Feature::new(ttf_parser::Tag::from_bytes(tag), *value, ..))
+        .collect();
+    let glyph_buf = rustybuzz::shape(face, &features, buf);
+
+    let first = glyphs.len();
+    let infos = glyph_buf.glyph_infos();
+    for (i, (info, pos)) in zip(infos, glyph_buf.glyph_positions()).enumerate() {
+        let cluster = info.cluster as usize;
+
+        // Letter spacing is added after the last glyph of each cluster.
+        let mut advance = scale * pos.x_advance as f32;
+        if infos.get(i + 1).map(|next| next.cluster) != Some(info.cluster) {
+            advance += style.letter_spacing;
+        }
+
+        glyphs.push(ShapedGlyph {
+            id: info.glyph_id as u16,
+            cluster: item.range.start + cluster,
+            span: item.span,
+            font,
+            level: item.level,
+            x: 0.0,
+            advance,
+            x_offset: scale * pos.x_offset as f32,
+            y_offset: scale * pos.y_offset as f32,
+            whitespace: text[cluster..].starts_with(char::is_whitespace),
+        });
+    }
+
+    // Right-to-left runs are shaped in visual order.
+    if item.is_rtl() {
+        glyphs[first..].reverse();
+    }
+    for glyph in &mut glyphs[first..] {
+        glyph.x = x;
+        x += glyph.advance;
+    }
+}
+
+pub(crate) fn is_line_break(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// A line produced by the line breaker, with glyph indices relative to its paragraph.
+struct BrokenLine {
+    glyphs: Range<usize>,
+    text: Range<usize>,
+    /// Whether the line ends its paragraph.
+    last: bool,
+    /// The bidi embedding level of the paragraph.
+    level: u8,
+}
+
+/// Finds the width of a range of glyphs, excluding trailing whitespace.
+fn visible_width(glyphs: &[ShapedGlyph], range: Range<usize>) -> f32 {
+    let end = visible_end(glyphs, range.clone());
+    if end == range.start {
+        return 0.0;
+    }
+    glyphs[end - 1].x + glyphs[end - 1].advance - glyphs[range.start].x
+}
+
+/// Finds the end of a range of glyphs with trailing whitespace removed.
+fn visible_end(glyphs: &[ShapedGlyph], range: Range<usize>) -> usize {
+    let mut end = range.end;
+    while end > range.start && glyphs[end - 1].whitespace {
+        end -= 1;
+    }
+    end
+}
+
+/// Breaks a shaped paragraph with the given bidi embedding level into lines no wider than
+/// `max_width`, at the given break opportunities where possible and between clusters otherwise.
+fn break_lines(
+    glyphs: &[ShapedGlyph],
+    text: Range<usize>,
+    opportunities: &[usize],
+    max_width: f32,
+    level: u8,
+    lines: &mut Vec<BrokenLine>,
+) {
+    // Index of the first glyph at or after a byte offset.
+    let glyph_at = |offset: usize| glyphs.partition_point(|glyph| glyph.cluster < offset);
+
+    let mut start = 0;
+    let mut text_start = text.start;
+    let mut fit: Option<(usize, usize)> = None;
+    for &offset in opportunities.iter().chain([&text.end]) {
+        let end = glyph_at(offset);
+
+        while visible_width(glyphs, start..end) > max_width {
+            let fit = fit.take().filter(|&(glyph, _)| glyph > start);
+            let (break_glyph, break_offset) = match fit {
+                Some(fit) => fit,
+                // No break opportunity fits on this line, so break the word at the last cluster
+                // boundary that fits, keeping at least one cluster on the line.
+                None => {
+                    let mut split = None;
+                    for i in start + 1..end {
+                        if glyphs[i].cluster != glyphs[i - 1].cluster {
+                            if split.is_some() && glyphs[i].x - glyphs[start].x > max_width {
+                                break;
+                            }
+                            split = Some(i);
+                        }
+                    }
+                    match split {
+                        Some(split) => (split, glyphs[split].cluster),
+                        None => break,
+                    }
+                }
+            };
+
+            lines.push(BrokenLine {
+                glyphs: start..break_glyph,
+                text: text_start..break_offset,
+                last: false,
+                level,
+            });
+            start = break_glyph;
+            text_start = break_offset;
+        }
+
+        fit = Some((end, offset));
+    }
+
+    lines.push(BrokenLine {
+        glyphs: start..glyphs.len(),
+        text: text_start..text.end,
+        last: true,
+        level,
+    });
+}
+
+impl TextLayout {
+    /// Lays out a paragraph of styled text, breaking it into lines according to `style`.
+    ///
+    /// Lines are broken at explicit line breaks, and at Unicode line break opportunities where
+    /// necessary to fit within the maximum width. The width of the layout is that of its widest
+    /// line, and lines are aligned within it. Spans of different sizes on the same line share a
+    /// baseline.
+    ///
+    /// Text is split into runs of a single script and direction for shaping, and each line is
+    /// displayed in visual order according to the Unicode Bidirectional Algorithm, with the base
+    /// direction of each paragraph determined by its first strong character.
+    pub fn attributed(text: &AttributedText, style: &ParagraphStyle) -> TextLayout {
+        let spans = text.spans();
+        let text = text.text();
+        let metrics: Vec<_> = spans
+            .iter()
+            .map(|span| SpanMetrics::new(&span.style, style.line_height))
+            .collect();
+
+        let bidi = BidiInfo::new(text, None);
+        let paragraph_level = |offset: usize| {
+            bidi.paragraphs
+                .iter()
+                .rfind(|paragraph| paragraph.range.start <= offset)
+                .map_or(0, |paragraph| paragraph.level.number())
+        };
+
+        let mut shaped = Vec::new();
+        let mut broken = Vec::new();
+        let mut opportunities = Vec::new();
+        let mut items = Vec::new();
+        let mut font_runs = Vec::new();
+
+        let mut start = 0;
+        for (offset, opportunity) in linebreaks(text) {
+            match opportunity {
+                BreakOpportunity::Allowed => opportunities.push(offset),
+                BreakOpportunity::Mandatory => {
+                    let content =
+                        start..start + text[start..offset].trim_end_matches(is_line_break).len();
+
+                    items.clear();
+                    itemize(text, content, spans, &bidi.levels, &mut items);
+
+                    let first_glyph = shaped.len();
+                    for item in &items {
+                        let style = &spans[item.span].style;
+                        font_runs.clear();
+                        split_fonts(text, item.range.clone(), style, &mut font_runs);
+                        for (range, font) in font_runs.drain(..) {
+                            let item = Item { range, ..*item };
+                            let x = shaped.last().map_or(0.0, |g: &ShapedGlyph| g.x + g.advance);
+                            shape(
+                                &text[item.range.clone()],
+                                &item,
+                                style,
+                                font,
+                                x,
+                                &mut shaped,
+                            );
+                        }
+                    }
+
+                    let first_line = broken.len();
+                    break_lines(
+                        &shaped[first_glyph..],
+                        start..offset,
+                        &opportunities,
+                        style.max_width,
+                        paragraph_level(start),
+                        &mut broken,
+                    );
+                    for line in &mut broken[first_line..] {
+                        line.glyphs =
+                            first_glyph + line.glyphs.start..first_glyph + line.glyphs.end;
+                    }
+
+                    opportunities.clear();
+                    start = offset;
+                }
+            }
+        }
+
+        // Empty text, and a line break at the end of the text, still occupy an empty line.
+        if text.is_empty() || text.ends_with(is_line_break) {
+            broken.push(BrokenLine {
+                glyphs: shaped.len()..shaped.len(),
+                text: text.len()..text.len(),
+                last: true,
+                level: paragraph_level(text.len()),
+            });
+        }
+
+        // The span in effect at a byte offset, for lines without glyphs.
+        let span_at =
+            |offset: usize| spans.iter().rposition(|span| span.range.start <= offset).unwrap_or(0);
+
+        // Glyphs appended to the last line when the text is truncated.
+        let mut ellipsis = Vec::new();
+        let mut ellipsis_width = 0.0;
+        if let Some(max_lines) = style.max_lines {
+            if broken.len() > max_lines {
+                broken.truncate(max_lines);
+
+                if let Some(line) = broken.last_mut() {
+                    line.last = true;
+
+                    // The ellipsis takes the style of the text it replaces and the direction of
+                    // the paragraph.
+                    let item = Item {
+                        range: line.text.end..line.text.end,
+                        span: span_at(line.text.end.saturating_sub(1)),
+                        level: line.level,
+                    };
+                    let span_style = &spans[item.span].style;
+                    let font = (0..1 + span_style.fallback.fonts().len())
+                        .find(|&index| {
+                            let font = span_style.font_at(index);
+                            style.ellipsis.chars().all(|c| font.has_glyph(c))
+                        })
+                        .unwrap_or(0);
+                    shape(&style.ellipsis, &item, span_style, font, 0.0, &mut ellipsis);
+                    ellipsis_width = ellipsis.last().map_or(0.0, |g| g.x + g.advance);
+
+                    // Remove whole clusters until the ellipsis fits.
+                    let start = line.glyphs.start;
+                    let mut end = visible_end(&shaped, line.glyphs.clone());
+                    while end > start
+                        && visible_width(&shaped, start..end) + ellipsis_width > style.max_width
+                    {
+                        let cluster = shaped[end - 1].cluster;
+                        while end > start && shaped[end - 1].cluster == cluster {
+                            end -= 1;
+                        }
+                        end = visible_end(&shaped, start..end);
+                    }
+                    line.glyphs.end = end;
+                }
+            }
+        }
+
+        let mut widths = Vec::with_capacity(broken.len());
+        for line in &broken {
+            widths.push(visible_width(&shaped, line.glyphs.clone()));
+        }
+        if let Some(last) = widths.last_mut() {
+            *last += ellipsis_width;
+        }
+        let width = widths.iter().copied().fold(0.0, f32::max);
+
+        let mut glyphs = Vec::with_capacity(shaped.len() + ellipsis.len());
+        let mut lines = Vec::with_capacity(broken.len());
+        let mut runs = Vec::new();
+        let mut clusters = Vec::new();
+        let mut line_glyphs = Vec::new();
+        let mut cluster_ends = Vec::new();
+        let mut top = 0.0;
+        for (index, (line, &line_width)) in zip(&broken, &widths).enumerate() {
+            line_glyphs.clear();
+            line_glyphs.extend_from_slice(&shaped[line.glyphs.clone()]);
+            let visible = visible_end(&line_glyphs, 0..line_glyphs.len());
+            if index == broken.len() - 1 {
+                line_glyphs.extend_from_slice(&ellipsis);
+            }
+
+            // The text shown on the line ends before any line break, or before the first glyph
+            // removed to make room for an ellipsis.
+            let content = &text[line.text.clone()];
+            let content_end = (line.text.start + content.trim_end_matches(is_line_break).len())
+                .min(shaped.get(line.glyphs.end).map_or(text.len(), |glyph| glyph.cluster));
+
+            // Each cluster's text extends to the start of the next cluster in logical order.
+            let count = line.glyphs.len();
+            cluster_ends.clear();
+            cluster_ends.resize(count, content_end);
+            for i in (0..count.saturating_sub(1)).rev() {
+                let next = &line_glyphs[i + 1];
+                if next.cluster != line_glyphs[i].cluster {
+                    cluster_ends[i] = next.cluster;
+                } else {
+                    cluster_ends[i] = cluster_ends[i + 1];
+                }
+            }
+
+            // The line is tall enough for the ascent and descent of every span on it, with the
+            // baseline placed below the tallest ascent.
+            let mut above: f32 = 0.0;
+            let mut below: f32 = 0.0;
+            let mut include = |span: usize| {
+                let metrics = &metrics[span];
+                above = above.max(metrics.ascent + metrics.half_leading);
+                below = below.max(metrics.descent + metrics.half_leading);
+            };
+            if line_glyphs.is_empty() {
+                include(span_at(line.text.start));
+            }
+            for glyph in &line_glyphs {
+                include(glyph.span);
+            }
+            let baseline = top + above;
+
+            let rtl = line.level % 2 == 1;
+            let x = match (style.align, rtl) {
+                (TextAlign::Left, _)
+                | (TextAlign::Start | TextAlign::Justify, false)
+                | (TextAlign::End, true) => 0.0,
+                (TextAlign::Center, _) => 0.5 * (width - line_width),
+                (TextAlign::Right, _)
+                | (TextAlign::Start | TextAlign::Justify, true)
+                | (TextAlign::End, false) => width - line_width,
+            };
+
+            // Justified lines distribute the remaining width evenly among their inner spaces.
+            let mut spacing = 0.0;
+            if style.align == TextAlign::Justify && !line.last {
+                let spaces = line_glyphs[..visible].iter().filter(|glyph| glyph.whitespace).count();
+                if spaces > 0 {
+                    spacing = (width - line_width) / spaces as f32;
+                }
+            }
+            let (x, line_width) = if spacing > 0.0 {
+                (0.0, width)
+            } else {
+                (x, line_width)
+            };
+
+            // Trailing whitespace takes the direction of the paragraph, so that it stays at the
+            // end of the line, and is then reordered along with the rest of the line.
+            let mut levels: Vec<u8> = line_glyphs.iter().map(|glyph| glyph.level).collect();
+            let trailing = visible..line.glyphs.len();
+            levels[trailing.clone()].fill(line.level);
+            let order = visual_order(&levels);
+
+            // In right-to-left paragraphs, the trailing whitespace hangs off the left edge.
+            let trailing_width: f32 = line_glyphs[trailing.clone()].iter().map(|g| g.advance).sum();
+            let mut pen = if rtl { x - trailing_width } else { x };
+
+            let first_glyph = glyphs.len();
+            let first_run = runs.len();
+            let first_cluster = clusters.len();
+            let mut run_visible = false;
+            for i in order {
+                let glyph = &line_glyphs[i];
+                let span_style = &spans[glyph.span].style;
+                let font = span_style.font_at(glyph.font);
+                if runs[first_run..].last().map(|run: &GlyphRun| (run.style, run.font.id))
+                    != Some((glyph.span, font.id))
+                {
+                    runs.push(GlyphRun {
+                        glyphs: glyphs.len()..glyphs.len(),
+                        style: glyph.span,
+                        font: font.clone(),
+                        x: pen,
+                        width: 0.0,
+                        baseline,
+                    });
+                    run_visible = false;
+                }
+
+                // Glyph outlines are positioned relative to the ascender of their font.
+                let ascent =
+                    span_style.size * font.face.ascender() as f32 / font.face.units_per_em() as f32;
+                glyphs.push(Glyph {
+                    id: glyph.id,
+                    x: pen + glyph.x_offset,
+                    y: baseline - ascent - glyph.y_offset,
+                });
+                let glyph_start = pen;
+                pen += glyph.advance;
+                if glyph.whitespace && i < visible {
+                    pen += spacing;
+                }
+
+                // The glyphs of the ellipsis form a single cluster standing for the text it
+                // replaces.
+                let cluster_text = if i < count {
+                    glyph.cluster..cluster_ends[i]
+                } else {
+                    content_end..text.len()
+                };
+                if clusters[first_cluster..].last().map(|cluster: &Cluster| &cluster.text)
+                    != Some(&cluster_text)
+                {
+                    clusters.push(Cluster {
+                        text: cluster_text,
+                        glyphs: glyphs.len() - 1..glyphs.len(),
+                        x: glyph_start,
+                        width: 0.0,
+                        rtl: levels[i] % 2 == 1,
+                    });
+                }
+                let cluster = clusters.last_mut().unwrap();
+                cluster.glyphs.end = glyphs.len();
+                cluster.width = pen - cluster.x;
+
+                // The extent of a run excludes trailing whitespace, which is never visible.
+                let run = runs.last_mut().unwrap();
+                run.glyphs.end = glyphs.len();
+                if !trailing.contains(&i) {
+                    if !run_visible {
+                        run.x = glyph_start;
+                        run_visible = true;
+                    }
+                    run.width = pen - run.x;
+                }
+            }
+
+            lines.push(Line {
+                glyphs: first_glyph..glyphs.len(),
+                clusters: first_cluster..clusters.len(),
+                text: line.text.clone(),
+                x,
+                y: top,
+                width: line_width,
+                height: above + below,
+                baseline,
+            });
+            top += above + below;
+        }
+
+        TextLayout {
+            text: text.to_owned(),
+            width,
+            height: top,
+            glyphs,
+            lines,
+            runs,
+            clusters,
+            styles: spans.iter().map(|span| span.style.clone()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::{Font, FontCollection};
+
+    const TEXT: &str = "The quick brown fox jumps over the lazy dog.";
+
+    fn font() -> Font {
+        Font::from_bytes(
+            include_bytes!("../examples/res/SourceSansPro-Regular.otf"),
+            0,
+        )
+        .unwrap()
+    }
+
+    fn text_of<'a>(text: &'a str, layout: &TextLayout) -> Vec<&'a str> {
+        layout.lines().iter().map(|line| &text[line.text.clone()]).collect()
+    }
+
+    #[test]
+    fn wrap() {
+        let font = font();
+        let mut style = ParagraphStyle::new();
+        style.max_width(100.0);
+        let layout = TextLayout::with_style(TEXT, &font, 16.0, &style);
+
+        assert!(layout.lines().len() > 2);
+        assert_eq!(text_of(TEXT, &layout).concat(), TEXT);
+        for line in layout.lines() {
+            assert!(line.width <= 100.0);
+        }
+        assert!(layout.width() <= 100.0);
+        assert_eq!(
+            layout.height(),
+            layout.lines().len() as f32 * layout.lines()[0].height
+        );
+
+        let single = TextLayout::new(TEXT, &font, 16.0);
+        assert_eq!(single.lines().len(), 1);
+        assert_eq!(single.glyphs().len(), layout.glyphs().len());
+    }
+
+    #[test]
+    fn break_word() {
+        let font = font();
+        let text = "Incomprehensibilities";
+        let mut style = ParagraphStyle::new();
+        style.max_width(40.0);
+        let layout = TextLayout::with_style(text, &font, 16.0, &style);
+
+        assert!(layout.lines().len() > 1);
+        assert_eq!(text_of(text, &layout).concat(), text);
+        for line in layout.lines() {
+            assert!(line.width <= 40.0);
+        }
+    }
+
+    #[test]
+    fn newlines() {
+        let font = font();
+        let text = "one\ntwo\r\n\nthree";
+        let layout = TextLayout::new(text, &font, 16.0);
+
+        assert_eq!(text_of(text, &layout), ["one\n", "two\r\n", "\n", "three"]);
+        assert_eq!(layout.lines()[2].glyphs.len(), 0);
+
+        let trailing = TextLayout::new("one\n", &font, 16.0);
+        assert_eq!(text_of("one\n", &trailing), ["one\n", ""]);
+        assert_eq!(layout.glyphs().len(), "onetwothree".len());
+
+        let lines = layout.lines();
+        assert!(lines[1].y > lines[0].y && lines[1].baseline > lines[0].baseline);
+    }
+
+    #[test]
+    fn align() {
+        let font = font();
+        let text = "A\nmuch longer line";
+        let layout = |align| {
+            let mut style = ParagraphStyle::new();
+            style.align(align);
+            TextLayout::with_style(text, &font, 16.0, &style)
+        };
+
+        let left = layout(TextAlign::Left);
+        let center = layout(TextAlign::Center);
+        let right = layout(TextAlign::Right);
+        let width = left.width();
+        assert_eq!(left.lines()[0].x, 0.0);
+        assert!((center.lines()[0].x - 0.5 * (width - center.lines()[0].width)).abs() < 1e-3);
+        assert!((right.lines()[0].x + right.lines()[0].width - width).abs() < 1e-3);
+        assert_eq!(right.lines()[1].x, 0.0);
+        assert_eq!(right.glyphs()[0].x - left.glyphs()[0].x, right.lines()[0].x);
+    }
+
+    #[test]
+    fn justify() {
+        let font = font();
+        let mut style = ParagraphStyle::new();
+        style.max_width(120.0).align(TextAlign::Justify);
+        let layout = TextLayout::with_style(TEXT, &font, 16.0, &style);
+
+        let (last, lines) = layout.lines().split_last().unwrap();
+        for line in lines {
+            assert_eq!(line.width, layout.width());
+        }
+        assert!(last.width < layout.width());
+
+        // The last visible glyph of a justified line ends at the right edge.
+        let space = font.face.glyph_index(' ').unwrap().0;
+        let line = &layout.lines()[0];
+        let glyphs = &layout.glyphs()[line.glyphs.clone()];
+        let glyph = glyphs.iter().rev().find(|glyph| glyph.id != space).unwrap();
+        let advance = font.face.glyph_hor_advance(rustybuzz::ttf_parser::GlyphId(glyph.id));
+        let end = glyph.x + advance.unwrap() as f32 * 16.0 / font.face.units_per_em() as f32;
+        assert!((end - layout.width()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn line_height() {
+        let font = font();
+        let text = "one\ntwo";
+        let normal = TextLayout::new(text, &font, 16.0);
+        let mut style = ParagraphStyle::new();
+        style.line_height(1.5);
+        let tall = TextLayout::with_style(text, &font, 16.0, &style);
+
+        assert!((tall.height() - 1.5 * normal.height()).abs() < 1e-3);
+        let (a, b) = (&normal.lines()[1], &tall.lines()[1]);
+        assert!((b.height - 1.5 * a.height).abs() < 1e-3);
+        assert!((b.baseline - b.y - (a.baseline - a.y) - 0.25 * a.height).abs() < 1e-3);
+    }
+
+    #[test]
+    fn ellipsis() {
+        let font = font();
+        let mut style = ParagraphStyle::new();
+        style.max_width(100.0).max_lines(Some(2));
+        let layout = TextLayout::with_style(TEXT, &font, 16.0, &style);
+        let ellipsis = font.face.glyph_index('\u{2026}').unwrap().0;
+
+        assert_eq!(layout.lines().len(), 2);
+        let last = &layout.lines()[1];
+        assert_eq!(layout.glyphs()[last.glyphs.end - 1].id, ellipsis);
+        assert!(last.width <= 100.0);
+
+        // Text that fits is not truncated.
+        style.max_lines(Some(10));
+        let layout = TextLayout::with_style(TEXT, &font, 16.0, &style);
+        assert!(layout.glyphs().iter().all(|glyph| glyph.id != ellipsis));
+    }
+
+    #[test]
+    fn attributed() {
+        let font = font();
+        let small = TextStyle::new(font.clone(), 12.0);
+        let mut large = TextStyle::new(font.clone(), 24.0);
+        large.letter_spacing(2.0);
+
+        let mut text = AttributedText::new("small ", &small);
+        text.push("large", &large).push(" small", &small);
+        let layout = TextLayout::attributed(&text, &ParagraphStyle::new());
+
+        let runs = layout.runs();
+        assert_eq!(runs.len(), 3);
+        assert_eq!(
+            runs.iter().map(|run| run.style).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+        assert!(runs.iter().all(|run| run.baseline == layout.lines()[0].baseline));
+        assert_eq!(runs[1].x, runs[0].x + runs[0].width);
+
+        // Glyphs of both sizes are positioned relative to the shared baseline.
+        let ascent =
+            |size: f32| size * font.face.ascender() as f32 / font.face.units_per_em() as f32;
+        let baseline = layout.lines()[0].baseline;
+        assert!((layout.glyphs()[0].y - (baseline - ascent(12.0))).abs() < 1e-3);
+        let glyph = layout.glyphs()[runs[1].glyphs.start];
+        assert!((glyph.y - (baseline - ascent(24.0))).abs() < 1e-3);
+        assert_eq!(
+            layout.height(),
+            TextLayout::new("large", &font, 24.0).height()
+        );
+
+        let unspaced = TextLayout::new("large", &font, 24.0);
+        assert!((runs[1].width - unspaced.width() - 5.0 * 2.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn bidi() {
+        let font = font();
+        let style = TextStyle::new(font.clone(), 16.0);
+        let run_styles = |text: &AttributedText, paragraph: &ParagraphStyle| {
+            let layout = TextLayout::attributed(text, paragraph);
+            let runs: Vec<_> = layout.runs().iter().map(|run| run.style).collect();
+            (layout, runs)
+        };
+
+        // Each word is a separate span, so the visual order of the runs is the visual order of
+        // the words.
+        let mut text = AttributedText::new("abc ", &style);
+        text.push("\u{5D0}\u{5D1} ", &style)
+            .push("\u{5D2}\u{5D3}", &style)
+            .push(" def", &style);
+        let (layout, runs) = run_styles(&text, &ParagraphStyle::new());
+        assert_eq!(runs, [0, 2, 1, 3]);
+        assert_eq!(layout.lines()[0].x, 0.0);
+        for pair in layout.runs().windows(2) {
+            assert!(pair[0].x < pair[1].x);
+        }
+
+        // A right-to-left paragraph is aligned to the right, with its trailing whitespace
+        // hanging off the left edge.
+        let mut text = AttributedText::new("\u{5D0}\u{5D1} ", &style);
+        text.push("abc ", &style).push("def ", &style).push("\u{5D2}\u{5D3}\n", &style);
+        text.push("x", &style);
+        let (layout, runs) = run_styles(&text, &ParagraphStyle::new());
+        assert_eq!(runs, [3, 2, 1, 2, 0, 4]);
+        let line = &layout.lines()[0];
+        assert!((line.x + line.width - layout.width()).abs() < 1e-3);
+
+        // The second paragraph is left-to-right.
+        assert_eq!(layout.lines()[1].x, 0.0);
+
+        let text = AttributedText::new("\u{5D0}\u{5D1} \u{5D2}\u{5D3}", &style);
+        let mut paragraph = ParagraphStyle::new();
+        paragraph.max_width(30.0);
+        let layout = TextLayout::attributed(&text, &paragraph);
+        let line = &layout.lines()[0];
+        assert_eq!(layout.lines().len(), 2);
+        assert!((line.x + line.width - layout.width()).abs() < 1e-3);
+        assert!(layout.glyphs()[line.glyphs.start].x < line.x);
+        assert!(layout.runs()[0].x >= line.x - 1e-3);
+    }
+
+    #[test]
+    fn fallback() {
+        let font = font();
+        let tiny = Font::from_bytes(
+            include_bytes!("../examples/res/SourceSansPro-Regular-Tiny.ttf"),
+            0,
+        )
+        .unwrap();
+        let mut style = TextStyle::new(tiny.clone(), 16.0);
+        style.fallback(FontCollection::new().push(font.clone()));
+
+        let text = AttributedText::new("Hello, world!", &style);
+        let layout = TextLayout::attributed(&text, &ParagraphStyle::new());
+        let fonts: Vec<_> = layout.runs().iter().map(|run| run.font.id).collect();
+        assert_eq!(fonts, [tiny.id, font.id, tiny.id, font.id]);
+        assert!(layout.glyphs().iter().all(|glyph| glyph.id != 0));
+    }
+
+    #[test]
+    fn features() {
+        let font = font();
+        let layout = |text: &str, features: &[([u8; 4], u32)]| {
+            let mut style = TextStyle::new(font.clone(), 16.0);
+            for &(tag, value) in features {
+                style.feature(tag, value);
+            }
+            TextLayout::attributed(&AttributedText::new(text, &style), &ParagraphStyle::new())
+        };
+
+        // The font's default figures are tabular.
+        assert_eq!(layout("111", &[]).width(), layout("000", &[]).width());
+        let proportional = [(*b"pnum", 1)];
+        let width = layout("111", &proportional).width();
+        assert_ne!(width, layout("000", &proportional).width());
+
+        let ids = |text: &str, features: &[([u8; 4], u32)]| -> Vec<u16> {
+            layout(text, features).glyphs().iter().map(|glyph| glyph.id).collect()
+        };
+        assert_eq!(ids("ff", &[]).len(), 1);
+        assert_eq!(ids("ff", &[(*b"liga", 0)]).len(), 2);
+        assert_ne!(ids("l", &[(*b"ss01", 1)]), ids("l", &[]));
+        assert_ne!(ids("a", &[(*b"smcp", 1)]), ids("a", &[]));
+    }
+
+    #[test]
+    fn empty() {
+        let font = font();
+        let layout = TextLayout::new("", &font, 16.0);
+        assert_eq!(layout.lines().len(), 1);
+        assert_eq!(layout.width(), 0.0);
+        assert!(layout.height() > 0.0);
+    }
+}
@@ -1,12 +1,135 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+use std::slice;
+
+use crate::curve::{Cubic, Line, Quadratic, Segment};
 use crate::geom::*;
 
-#[derive(Clone)]
+/// The distance of the control points from the endpoints, relative to the radius, for a cubic
+/// approximating a quarter of a circle.
+const QUARTER_CIRCLE_K: f32 = 0.552_284_8;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Path {
     pub(crate) verbs: Vec<Verb>,
     pub(crate) points: Vec<Point>,
 }
 
-#[derive(Copy, Clone)]
+/// Determines which regions are considered to be inside of a path.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum FillRule {
+    /// A point is inside the path if its winding number is nonzero.
+    #[default]
+    NonZero,
+    /// A point is inside the path if its winding number is odd.
+    EvenOdd,
+}
+
+/// The shape used to connect adjacent segments of a stroked path.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum LineJoin {
+    /// Extend the outer edges of the segments until they meet, falling back to [`LineJoin::Bevel`]
+    /// if the resulting miter would exceed the miter limit.
+    #[default]
+    Miter,
+    /// Connect the segments with a circular arc.
+    Round,
+    /// Connect the outer corners of the segments with a straight line.
+    Bevel,
+}
+
+/// The shape used at the ends of open subpaths when stroking.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum LineCap {
+    /// End the stroke exactly at the endpoint.
+    #[default]
+    Butt,
+    /// Extend the stroke with a semicircle centered on the endpoint.
+    Round,
+    /// Extend the stroke by half of the stroke width past the endpoint.
+    Square,
+}
+
+/// Parameters for stroking a path.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrokeStyle {
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /// The maximum ratio of miter length to stroke width for [`LineJoin::Miter`].
+    pub miter_limit: f32,
+    /// Alternating lengths of dashes and gaps. An empty list produces a solid stroke.
+    pub dashes: Vec<f32>,
+    /// The distance into the dash pattern at which each subpath starts.
+    pub dash_offset: f32,
+}
+
+impl StrokeStyle {
+    /// Constructs a stroke style with the given width, miter joins with a miter limit of 4, and
+    /// butt caps.
+    #[inline]
+    pub fn new(width: f32) -> StrokeStyle {
+        StrokeStyle {
+            width,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            miter_limit: 4.0,
+            dashes: Vec::new(),
+            dash_offset: 0.0,
+        }
+    }
+
+    #[inline]
+    pub fn join(&mut self, join: LineJoin) -> &mut Self {
+        self.join = join;
+        self
+    }
+
+    #[inline]
+    pub fn cap(&mut self, cap: LineCap) -> &mut Self {
+        self.cap = cap;
+        self
+    }
+
+    #[inline]
+    pub fn miter_limit(&mut self, miter_limit: f32) -> &mut Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    /// Sets the dash pattern and the offset into it.
+    ///
+    /// As in SVG, a list with an odd number of entries is repeated to produce an even number, and
+    /// a list containing negative values or summing to zero is ignored. A pattern too fine to be
+    /// resolved along the path is ignored as well, and the path is stroked solid.
+    #[inline]
+    pub fn dashes(&mut self, dashes: &[f32], offset: f32) -> &mut Self {
+        self.dashes.clear();
+        self.dashes.extend_from_slice(dashes);
+        self.dash_offset = offset;
+        self
+    }
+
+    /// Computes how far the stroke can extend from the path, as a multiple of the stroke width.
+    #[inline]
+    pub(crate) fn extent(&self) -> f32 {
+        let mut extent: f32 = 0.5;
+        if self.join == LineJoin::Miter {
+            extent = extent.max(0.5 * self.miter_limit);
+        }
+        if self.cap == LineCap::Square {
+            extent = extent.max(0.5 * std::f32::consts::SQRT_2);
+        }
+        extent
+    }
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Verb {
     Move,
     Line,
@@ -15,7 +138,7 @@ pub enum Verb {
     Close,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Command {
     Move(Point),
     Line(Point),
@@ -33,6 +156,130 @@ impl Path {
         }
     }
 
+    /// Constructs a rectangle with its top-left corner at `pos`.
+    pub fn rect(pos: Point, width: f32, height: f32) -> Path {
+        let mut path = Path::new();
+        path.move_to(pos);
+        path.line_to(Point::new(pos.x + width, pos.y));
+        path.line_to(Point::new(pos.x + width, pos.y + height));
+        path.line_to(Point::new(pos.x, pos.y + height));
+        path.close();
+        path
+    }
+
+    /// Constructs a rectangle with rounded corners.
+    ///
+    /// The corner radii are given in the order top-left, top-right, bottom-right, bottom-left. As
+    /// in CSS, if the radii of two adjacent corners add up to more than the length of the side
+    /// between them, all radii are scaled down proportionally.
+    pub fn rounded_rect(pos: Point, width: f32, height: f32, radii: [f32; 4]) -> Path {
+        let [top_left, top_right, bottom_right, bottom_left] = radii.map(|r| r.max(0.0));
+
+        let mut scale: f32 = 1.0;
+        for (sum, side) in [
+            (top_left + top_right, width),
+            (bottom_left + bottom_right, width),
+            (top_left + bottom_left, height),
+            (top_right + bottom_right, height),
+        ] {
+            if sum > side {
+                scale = scale.min(side / sum);
+            }
+        }
+        let [top_left, top_right, bottom_right, bottom_left] =
+            [top_left, top_right, bottom_right, bottom_left].map(|r| scale * r);
+
+        let (left, top) = (pos.x, pos.y);
+        let (right, bottom) = (pos.x + width, pos.y + height);
+        let k = QUARTER_CIRCLE_K;
+
+        let mut path = Path::new();
+        path.move_to(Point::new(left + top_left, top));
+        path.line_to(Point::new(right - top_right, top));
+        if top_right > 0.0 {
+            path.cubic_to(
+                Point::new(right - (1.0 - k) * top_right, top),
+                Point::new(right, top + (1.0 - k) * top_right),
+                Point::new(right, top + top_right),
+            );
+        }
+        path.line_to(Point::new(right, bottom - bottom_right));
+        if bottom_right > 0.0 {
+            path.cubic_to(
+                Point::new(right, bottom - (1.0 - k) * bottom_right),
+                Point::new(right - (1.0 - k) * bottom_right, bottom),
+                Point::new(right - bottom_right, bottom),
+            );
+        }
+        path.line_to(Point::new(left + bottom_left, bottom));
+        if bottom_left > 0.0 {
+            path.cubic_to(
+                Point::new(left + (1.0 - k) * bottom_left, bottom),
+                Point::new(left, bottom - (1.0 - k) * bottom_left),
+                Point::new(left, bottom - bottom_left),
+            );
+        }
+        path.line_to(Point::new(left, top + top_left));
+        if top_left > 0.0 {
+            path.cubic_to(
+                Point::new(left, top + (1.0 - k) * top_left),
+                Point::new(left + (1.0 - k) * top_left, top),
+                Point::new(left + top_left, top),
+            );
+        }
+        path.close();
+        path
+    }
+
+    /// Constructs a circle.
+    pub fn circle(center: Point, radius: f32) -> Path {
+        Path::ellipse(center, Point::new(radius, radius))
+    }
+
+    /// Constructs an axis-aligned ellipse with the given horizontal and vertical radii.
+    pub fn ellipse(center: Point, radii: Point) -> Path {
+        let k = QUARTER_CIRCLE_K;
+        let (rx, ry) = (radii.x, radii.y);
+
+        let mut path = Path::new();
+        path.move_to(center + Point::new(rx, 0.0));
+        path.cubic_to(
+            center + Point::new(rx, k * ry),
+            center + Point::new(k * rx, ry),
+            center + Point::new(0.0, ry),
+        );
+        path.cubic_to(
+            center + Point::new(-k * rx, ry),
+            center + Point::new(-rx, k * ry),
+            center + Point::new(-rx, 0.0),
+        );
+        path.cubic_to(
+            center + Point::new(-rx, -k * ry),
+            center + Point::new(-k * rx, -ry),
+            center + Point::new(0.0, -ry),
+        );
+        path.cubic_to(
+            center + Point::new(k * rx, -ry),
+            center + Point::new(rx, -k * ry),
+            center + Point::new(rx, 0.0),
+        );
+        path.close();
+        path
+    }
+
+    /// Constructs a closed polygon through the given points.
+    pub fn polygon(points: &[Point]) -> Path {
+        let mut path = Path::new();
+        if let Some((&first, rest)) = points.split_first() {
+            path.move_to(first);
+            for &point in rest {
+                path.line_to(point);
+            }
+            path.close();
+        }
+        path
+    }
+
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.points.is_empty()
@@ -111,12 +358,201 @@ impl Path {
         self
     }
 
+    /// Appends an elliptical arc from the current point to `end`, following the semantics of the
+    /// SVG `A` path command. The arc is approximated with cubic Béziers.
+    ///
+    /// `x_rotation` is the rotation of the ellipse's x-axis in radians. Of the four candidate arcs,
+    /// `large_arc` selects one spanning more than 180 degrees and `sweep` selects one traversed in
+    /// the direction of increasing angle. Radii which are too small to reach `end` are scaled up,
+    /// and a zero radius produces a straight line. If the path has no current point, the arc
+    /// starts a new subpath at the origin.
+    pub fn arc_to(
+        &mut self,
+        radii: Point,
+        x_rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        end: Point,
+    ) -> &mut Self {
+        let start = match self.current_point() {
+            Some(point) => point,
+            None => {
+                let origin = Point::new(0.0, 0.0);
+                self.move_to(origin);
+                origin
+            }
+        };
+        if start == end {
+            return self;
+        }
+
+        let mut rx = radii.x.abs();
+        let mut ry = radii.y.abs();
+        if rx == 0.0 || ry == 0.0 {
+            return self.line_to(end);
+        }
+
+        // Conversion from endpoint to center parameterization, following the SVG specification.
+        let (sin, cos) = x_rotation.sin_cos();
+        let half = 0.5 * (start - end);
+        let p = Point::new(cos * half.x + sin * half.y, -sin * half.x + cos * half.y);
+
+        let lambda = (p.x * p.x) / (rx * rx) + (p.y * p.y) / (ry * ry);
+        if lambda > 1.0 {
+            let scale = lambda.sqrt();
+            rx *= scale;
+            ry *= scale;
+        }
+
+        let num = rx * rx * ry * ry - rx * rx * p.y * p.y - ry * ry * p.x * p.x;
+        let den = rx * rx * p.y * p.y + ry * ry * p.x * p.x;
+        let mut coeff = (num / den).max(0.0).sqrt();
+        if large_arc == sweep {
+            coeff = -coeff;
+        }
+        let c = Point::new(coeff * rx * p.y / ry, -coeff * ry * p.x / rx);
+
+        let mid = 0.5 * (start + end);
+        let center = Point::new(cos * c.x - sin * c.y + mid.x, sin * c.x + cos * c.y + mid.y);
+
+        let u = Point::new((p.x - c.x) / rx, (p.y - c.y) / ry);
+        let v = Point::new((-p.x - c.x) / rx, (-p.y - c.y) / ry);
+        let start_angle = u.y.atan2(u.x);
+        let mut sweep_angle = u.cross(v).atan2(u.dot(v));
+        if !sweep && sweep_angle > 0.0 {
+            sweep_angle -= 2.0 * PI;
+        } else if sweep && sweep_angle < 0.0 {
+            sweep_angle += 2.0 * PI;
+        }
+
+        let radii = Point::new(rx, ry);
+        self.arc_segments(
+            center,
+            radii,
+            x_rotation,
+            start_angle,
+            sweep_angle,
+            Some(end),
+        )
+    }
+
+    /// Appends an arc of the ellipse with the given center and radii, whose x-axis is rotated by
+    /// `rotation` radians. The arc is approximated with cubic Béziers.
+    ///
+    /// The arc begins at angle `start` and spans `sweep` radians, where positive angles run from
+    /// the ellipse's x-axis towards its y-axis. If the path has a current point, it is connected to
+    /// the start of the arc with a line; otherwise a new subpath is started. A sweep of more than a
+    /// full turn is limited to one, and an arc with a non-finite `start` or `sweep` is ignored.
+    pub fn elliptical_arc(
+        &mut self,
+        center: Point,
+        radii: Point,
+        rotation: f32,
+        start: f32,
+        sweep: f32,
+    ) -> &mut Self {
+        if !start.is_finite() || !sweep.is_finite() {
+            return self;
+        }
+
+        let transform = ellipse_transform(center, radii, rotation);
+        let start_point = transform * Point::new(start.cos(), start.sin());
+        match self.current_point() {
+            None => {
+                self.move_to(start_point);
+            }
+            Some(current) if current != start_point => {
+                self.line_to(start_point);
+            }
+            Some(_) => {}
+        }
+
+        self.arc_segments(center, radii, rotation, start, sweep, None)
+    }
+
+    fn arc_segments(
+        &mut self,
+        center: Point,
+        radii: Point,
+        rotation: f32,
+        start: f32,
+        sweep: f32,
+        end: Option<Point>,
+    ) -> &mut Self {
+        let transform = ellipse_transform(center, radii, rotation);
+
+        // Split the arc into pieces of at most a quarter turn, each approximated by one cubic.
+        let sweep = sweep.clamp(-2.0 * PI, 2.0 * PI);
+        let segments = (sweep.abs() / FRAC_PI_2).ceil().max(1.0) as usize;
+        let step = sweep / segments as f32;
+        let k = (4.0 / 3.0) * (0.25 * step).tan();
+
+        let mut angle = start;
+        for i in 0..segments {
+            let (sin0, cos0) = angle.sin_cos();
+            let (sin1, cos1) = (angle + step).sin_cos();
+
+            let p1 = Point::new(cos0 - k * sin0, sin0 + k * cos0);
+            let p2 = Point::new(cos1 + k * sin1, sin1 - k * cos1);
+            let p3 = match end {
+                Some(end) if i == segments - 1 => end,
+                _ => transform * Point::new(cos1, sin1),
+            };
+            self.cubic_to(transform * p1, transform * p2, p3);
+
+            angle += step;
+        }
+
+        self
+    }
+
     #[inline]
     pub fn close(&mut self) -> &mut Self {
         self.verbs.push(Verb::Close);
         self
     }
 
+    /// Returns an iterator over the commands of the path.
+    #[inline]
+    pub fn commands(&self) -> Commands<'_> {
+        Commands {
+            verbs: self.verbs.iter(),
+            points: self.points.iter(),
+        }
+    }
+
+    /// Finds the point at which the next command will start, if any.
+    fn current_point(&self) -> Option<Point> {
+        match self.verbs.last()? {
+            // After a close, the current point is the start of the closed subpath.
+            Verb::Close => {
+                let mut index = self.points.len();
+                for verb in self.verbs.iter().rev() {
+                    match verb {
+                        Verb::Move => return Some(self.points[index - 1]),
+                        Verb::Line => index -= 1,
+                        Verb::Quadratic => index -= 2,
+                        Verb::Cubic => index -= 3,
+                        Verb::Close => {}
+                    }
+                }
+                None
+            }
+            _ => self.points.last().copied(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn segments(&self, implicit_close: bool) -> Segments<'_> {
+        Segments {
+            verbs: self.verbs.iter(),
+            points: self.points.iter(),
+            first: Point::new(0.0, 0.0),
+            prev: Point::new(0.0, 0.0),
+            implicit_close,
+        }
+    }
+
     #[inline]
     pub fn push(&mut self, command: Command) {
         match command {
@@ -139,8 +575,277 @@ impl Path {
     }
 }
 
+/// Constructs the transform mapping the unit circle onto an ellipse.
+#[inline]
+fn ellipse_transform(center: Point, radii: Point, rotation: f32) -> Affine {
+    let (sin, cos) = rotation.sin_cos();
+    Affine::translate(center.x, center.y)
+        * Affine::new([cos, -sin, 0.0, sin, cos, 0.0])
+        * Affine::new([radii.x, 0.0, 0.0, 0.0, radii.y, 0.0])
+}
+
+/// An iterator over the [`Command`]s of a [`Path`].
+#[derive(Clone)]
+pub struct Commands<'a> {
+    verbs: slice::Iter<'a, Verb>,
+    points: slice::Iter<'a, Point>,
+}
+
+impl<'a> Iterator for Commands<'a> {
+    type Item = Command;
+
+    #[inline]
+    fn next(&mut self) -> Option<Command> {
+        let mut point = || *self.points.next().unwrap();
+
+        Some(match *self.verbs.next()? {
+            Verb::Move => Command::Move(point()),
+            Verb::Line => Command::Line(point()),
+            Verb::Quadratic => {
+                let control = point();
+                Command::Quadratic(control, point())
+            }
+            Verb::Cubic => {
+                let control1 = point();
+                let control2 = point();
+                Command::Cubic(control1, control2, point())
+            }
+            Verb::Close => Command::Close,
+        })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.verbs.size_hint()
+    }
+}
+
+impl<'a> IntoIterator for &'a Path {
+    type Item = Command;
+    type IntoIter = Commands<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Commands<'a> {
+        self.commands()
+    }
+}
+
+/// An iterator over the segments of a path, including the closing line of each closed subpath
+/// and, if `implicit_close` is set, the closing line of each open subpath.
+pub(crate) struct Segments<'a> {
+    verbs: slice::Iter<'a, Verb>,
+    points: slice::Iter<'a, Point>,
+    first: Point,
+    prev: Point,
+    implicit_close: bool,
+}
+
+impl<'a> Segments<'a> {
+    #[inline]
+    fn close(&mut self) -> Option<Segment> {
+        if self.prev == self.first {
+            return None;
+        }
+
+        let line = Line {
+            p0: self.prev,
+            p1: self.first,
+        };
+        self.prev = self.first;
+
+        Some(Segment::Line(line))
+    }
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Segment;
+
+    fn next(&mut self) -> Option<Segment> {
+        loop {
+            let Some(verb) = self.verbs.next() else {
+                return if self.implicit_close {
+                    self.close()
+                } else {
+                    None
+                };
+            };
+
+            match *verb {
+                Verb::Move => {
+                    let closing = if self.implicit_close {
+                        self.close()
+                    } else {
+                        None
+                    };
+
+                    self.first = *self.points.next().unwrap();
+                    self.prev = self.first;
+
+                    if closing.is_some() {
+                        return closing;
+                    }
+                }
+                Verb::Line => {
+                    let p1 = *self.points.next().unwrap();
+                    let line = Line { p0: self.prev, p1 };
+                    self.prev = p1;
+                    return Some(Segment::Line(line));
+                }
+                Verb::Quadratic => {
+                    let p1 = *self.points.next().unwrap();
+                    let p2 = *self.points.next().unwrap();
+                    let quadratic = Quadratic {
+                        p0: self.prev,
+                        p1,
+                        p2,
+                    };
+                    self.prev = p2;
+                    return Some(Segment::Quadratic(quadratic));
+                }
+                Verb::Cubic => {
+                    let p1 = *self.points.next().unwrap();
+                    let p2 = *self.points.next().unwrap();
+                    let p3 = *self.points.next().unwrap();
+                    let cubic = Cubic {
+                        p0: self.prev,
+                        p1,
+                        p2,
+                        p3,
+                    };
+                    self.prev = p3;
+                    return Some(Segment::Cubic(cubic));
+                }
+                Verb::Close => {
+                    if let Some(line) = self.close() {
+                        return Some(line);
+                    }
+                }
+            }
+        }
+    }
+}
+
 impl Default for Path {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: Point, b: Point) -> bool {
+        a.distance(b) < 1e-4
+    }
+
+    #[test]
+    fn arc_to() {
+        // Radii too small to reach the endpoint are scaled up to a half circle.
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.arc_to(
+            Point::new(1.0, 1.0),
+            0.0,
+            false,
+            true,
+            Point::new(10.0, 0.0),
+        );
+        assert_eq!(path.verbs, [Verb::Move, Verb::Cubic, Verb::Cubic]);
+        assert!(approx_eq(path.points[3], Point::new(5.0, -5.0)));
+        assert_eq!(*path.points.last().unwrap(), Point::new(10.0, 0.0));
+
+        // The large arc of a rotated ellipse, continuing from the start of a closed subpath.
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.line_to(Point::new(5.0, 5.0));
+        path.close();
+        path.arc_to(
+            Point::new(2.0, 1.0),
+            FRAC_PI_2,
+            true,
+            false,
+            Point::new(1.0, 2.0),
+        );
+        assert_eq!(path.verbs[3..], [Verb::Cubic, Verb::Cubic, Verb::Cubic]);
+
+        let mut arc = Path::new();
+        arc.move_to(Point::new(0.0, 0.0));
+        arc.arc_to(
+            Point::new(2.0, 1.0),
+            FRAC_PI_2,
+            true,
+            false,
+            Point::new(1.0, 2.0),
+        );
+        assert_eq!(path.points[2..], arc.points[1..]);
+        assert_eq!(
+            arc.bounds(),
+            Some(Rect::new(Point::new(-1.0, 0.0), Point::new(1.0, 4.0)))
+        );
+
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.arc_to(
+            Point::new(0.0, 1.0),
+            0.0,
+            false,
+            false,
+            Point::new(1.0, 1.0),
+        );
+        assert_eq!(path.verbs, [Verb::Move, Verb::Line]);
+
+        // Without a current point, the arc starts at the origin.
+        let mut path = Path::new();
+        path.arc_to(
+            Point::new(5.0, 5.0),
+            0.0,
+            false,
+            true,
+            Point::new(10.0, 0.0),
+        );
+        assert_eq!(path.verbs, [Verb::Move, Verb::Cubic, Verb::Cubic]);
+        assert_eq!(path.points[0], Point::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn elliptical_arc() {
+        let mut path = Path::new();
+        path.elliptical_arc(
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 1.0),
+            FRAC_PI_2,
+            0.0,
+            PI,
+        );
+        assert_eq!(path.verbs, [Verb::Move, Verb::Cubic, Verb::Cubic]);
+        assert!(approx_eq(path.points[0], Point::new(0.0, 2.0)));
+        assert!(approx_eq(path.points[3], Point::new(-1.0, 0.0)));
+        assert!(approx_eq(path.points[6], Point::new(0.0, -2.0)));
+
+        // An existing current point is connected to the start of the arc.
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.elliptical_arc(
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            0.0,
+            PI,
+            -FRAC_PI_2,
+        );
+        assert_eq!(path.verbs, [Verb::Move, Verb::Line, Verb::Cubic]);
+        assert!(approx_eq(path.points[1], Point::new(-1.0, 0.0)));
+        assert!(approx_eq(path.points[4], Point::new(0.0, 1.0)));
+
+        // Sweeps are limited to a full turn, and non-finite sweeps are ignored.
+        for sweep in [1e30, f32::INFINITY, f32::NAN] {
+            let mut path = Path::new();
+            path.elliptical_arc(Point::new(0.0, 0.0), Point::new(1.0, 1.0), 0.0, 0.0, sweep);
+            let cubics = if sweep.is_finite() { 4 } else { 0 };
+            assert_eq!(
+                path.verbs.iter().filter(|&&v| v == Verb::Cubic).count(),
+                cubics
+            );
+        }
+    }
+}
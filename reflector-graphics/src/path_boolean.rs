@@ -0,0 +1,697 @@
+use std::collections::HashMap;
+use std::ops;
+
+use crate::curve::{subcurve, Curve, Segment};
+use crate::geom::Point;
+use crate::path::{FillRule, Path};
+
+const MAX_SEGMENTS: usize = 1024;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Op {
+    Union,
+    Intersection,
+    Difference,
+    Xor,
+}
+
+impl Op {
+    #[inline]
+    fn apply(self, a: bool, b: bool) -> bool {
+        match self {
+            Op::Union => a || b,
+            Op::Intersection => a && b,
+            Op::Difference => a && !b,
+            Op::Xor => a != b,
+        }
+    }
+}
+
+/// A double-precision point, used to keep intersection computations stable.
+#[derive(Copy, Clone, PartialEq)]
+struct Vec2 {
+    x: f64,
+    y: f64,
+}
+
+impl Vec2 {
+    #[inline]
+    fn new(x: f64, y: f64) -> Vec2 {
+        Vec2 { x, y }
+    }
+
+    /// Rounds the point to a grid with the given spacing, so that points which should coincide
+    /// compare equal.
+    #[inline]
+    fn snap(self, grid: f64) -> Vec2 {
+        // Adding zero normalizes negative zero, so that equal points have equal bit patterns.
+        Vec2::new(
+            (self.x / grid).round() * grid + 0.0,
+            (self.y / grid).round() * grid + 0.0,
+        )
+    }
+
+    #[inline]
+    fn dot(self, other: Vec2) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    #[inline]
+    fn cross(self, other: Vec2) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    #[inline]
+    fn distance(self, other: Vec2) -> f64 {
+        let diff = other - self;
+        diff.dot(diff).sqrt()
+    }
+
+    #[inline]
+    fn to_point(self) -> Point {
+        Point::new(self.x as f32, self.y as f32)
+    }
+}
+
+impl From<Point> for Vec2 {
+    #[inline]
+    fn from(point: Point) -> Vec2 {
+        Vec2::new(point.x as f64, point.y as f64)
+    }
+}
+
+impl ops::Add for Vec2 {
+    type Output = Vec2;
+
+    #[inline]
+    fn add(self, rhs: Vec2) -> Vec2 {
+        Vec2::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl ops::Sub for Vec2 {
+    type Output = Vec2;
+
+    #[inline]
+    fn sub(self, rhs: Vec2) -> Vec2 {
+        Vec2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl ops::Mul<Vec2> for f64 {
+    type Output = Vec2;
+
+    #[inline]
+    fn mul(self, rhs: Vec2) -> Vec2 {
+        Vec2::new(self * rhs.x, self * rhs.y)
+    }
+}
+
+/// A straight edge of a flattened operand, remembering the part of the original segment it
+/// approximates so that unsplit curves can be reconstructed in the output.
+#[derive(Copy, Clone)]
+struct Edge {
+    p0: Vec2,
+    p1: Vec2,
+    operand: usize,
+    curve: usize,
+    t0: f32,
+    t1: f32,
+}
+
+/// An edge of the planar arrangement of both operands, with `v0 < v1`. Coincident edges are
+/// merged, so `winding` holds the summed winding contribution of each operand when traversed
+/// from `v0` to `v1`.
+struct Piece {
+    v0: usize,
+    v1: usize,
+    winding: [i32; 2],
+    curve: usize,
+    t0: f32,
+    t1: f32,
+}
+
+/// A directed edge of the result, with the region to its right (in a y-down coordinate system)
+/// inside.
+struct Boundary {
+    from: usize,
+    to: usize,
+    curve: usize,
+    t_from: f32,
+    t_to: f32,
+}
+
+fn flatten(
+    path: &Path,
+    operand: usize,
+    tolerance: f32,
+    grid: f64,
+    curves: &mut Vec<Segment>,
+    edges: &mut Vec<Edge>,
+) {
+    for segment in path.segments(true) {
+        let curve = curves.len();
+        curves.push(segment);
+
+        let segments = match segment {
+            Segment::Line(_) => 1,
+            _ => segment.segments_for_tolerance(tolerance).clamp(1, MAX_SEGMENTS),
+        };
+
+        let mut prev = Vec2::from(segment.start()).snap(grid);
+        let mut prev_t = 0.0;
+        for i in 1..=segments {
+            let (t, point) = if i == segments {
+                (1.0, segment.end())
+            } else {
+                let t = i as f32 / segments as f32;
+                (t, segment.eval(t))
+            };
+
+            let point = Vec2::from(point).snap(grid);
+            if point != prev {
+                edges.push(Edge {
+                    p0: prev,
+                    p1: point,
+                    operand,
+                    curve,
+                    t0: prev_t,
+                    t1: t,
+                });
+                prev = point;
+                prev_t = t;
+            }
+        }
+    }
+}
+
+/// Finds the parameter at which `point` lies on the interior of `edge`, if any.
+#[inline]
+fn project(edge: &Edge, point: Vec2, grid: f64) -> Option<f64> {
+    if point.distance(edge.p0) <= grid || point.distance(edge.p1) <= grid {
+        return None;
+    }
+
+    let dir = edge.p1 - edge.p0;
+    let t = (point - edge.p0).dot(dir) / dir.dot(dir);
+    if t <= 0.0 || t >= 1.0 || point.distance(edge.p0 + t * dir) > grid {
+        return None;
+    }
+
+    Some(t)
+}
+
+/// Records the points at which two edges touch or cross as split points of each edge.
+fn intersect(edges: &[Edge], a: usize, b: usize, grid: f64, splits: &mut [Vec<(f64, Vec2)>]) {
+    let (ea, eb) = (&edges[a], &edges[b]);
+    if ea.p0.y.max(ea.p1.y) < eb.p0.y.min(eb.p1.y) - grid
+        || eb.p0.y.max(eb.p1.y) < ea.p0.y.min(ea.p1.y) - grid
+    {
+        return;
+    }
+
+    // Endpoints lying on the other edge cover both T-junctions and collinear overlaps.
+    let mut touching = false;
+    for point in [eb.p0, eb.p1] {
+        if let Some(t) = project(ea, point, grid) {
+            splits[a].push((t, point));
+            touching = true;
+        }
+    }
+    for point in [ea.p0, ea.p1] {
+        if let Some(t) = project(eb, point, grid) {
+            splits[b].push((t, point));
+            touching = true;
+        }
+    }
+    if touching {
+        return;
+    }
+
+    let r = ea.p1 - ea.p0;
+    let s = eb.p1 - eb.p0;
+    let denom = r.cross(s);
+    if denom.abs() <= 1e-12 * r.dot(r).sqrt() * s.dot(s).sqrt() {
+        return;
+    }
+
+    let qp = eb.p0 - ea.p0;
+    let t = qp.cross(s) / denom;
+    let u = qp.cross(r) / denom;
+    if t <= 0.0 || t >= 1.0 || u <= 0.0 || u >= 1.0 {
+        return;
+    }
+
+    let point = (ea.p0 + t * r).snap(grid);
+    if [ea.p0, ea.p1, eb.p0, eb.p1].iter().any(|&end| end.distance(point) <= grid) {
+        return;
+    }
+
+    splits[a].push((t, point));
+    splits[b].push((u, point));
+}
+
+/// Computes the winding contribution of the edge from `p0` to `p1` for a ray cast from `point` in
+/// the positive x direction.
+#[inline]
+fn ray_crossing(point: Vec2, p0: Vec2, p1: Vec2) -> i32 {
+    // Half-open intervals ensure that crossings at shared endpoints are counted once.
+    let direction = if p0.y <= point.y && point.y < p1.y {
+        1
+    } else if p1.y <= point.y && point.y < p0.y {
+        -1
+    } else {
+        return 0;
+    };
+
+    let x = p0.x + (point.y - p0.y) / (p1.y - p0.y) * (p1.x - p0.x);
+    if x > point.x {
+        direction
+    } else {
+        0
+    }
+}
+
+#[inline]
+fn is_inside(winding: i32, fill_rule: FillRule) -> bool {
+    match fill_rule {
+        FillRule::NonZero => winding != 0,
+        FillRule::EvenOdd => winding % 2 != 0,
+    }
+}
+
+fn boolean(a: &Path, b: &Path, op: Op, fill_rule: FillRule, tolerance: f32) -> Path {
+    let tolerance = tolerance.max(1e-4);
+    let grid = 1e-3 * tolerance as f64;
+
+    let mut curves = Vec::new();
+    let mut edges = Vec::new();
+    flatten(a, 0, tolerance, grid, &mut curves, &mut edges);
+    flatten(b, 1, tolerance, grid, &mut curves, &mut edges);
+
+    // Split every edge at its intersections with all other edges, including those of the same
+    // operand, so that the winding numbers on either side are constant along each piece.
+    let mut splits = vec![Vec::new(); edges.len()];
+    let min_x = |edge: &Edge| edge.p0.x.min(edge.p1.x);
+    let mut order: Vec<usize> = (0..edges.len()).collect();
+    order.sort_by(|&i, &j| min_x(&edges[i]).total_cmp(&min_x(&edges[j])));
+    for (index, &i) in order.iter().enumerate() {
+        let max_x = edges[i].p0.x.max(edges[i].p1.x);
+        for &j in &order[index + 1..] {
+            if min_x(&edges[j]) > max_x + grid {
+                break;
+            }
+            intersect(&edges, i, j, grid, &mut splits);
+        }
+    }
+
+    let mut vertices = Vec::new();
+    let mut vertex_map = HashMap::new();
+    let mut vertex = |point: Vec2| {
+        *vertex_map.entry((point.x.to_bits(), point.y.to_bits())).or_insert_with(|| {
+            vertices.push(point);
+            vertices.len() - 1
+        })
+    };
+
+    let mut pieces: Vec<Piece> = Vec::new();
+    let mut piece_map = HashMap::new();
+    for (edge, split) in edges.iter().zip(splits.iter_mut()) {
+        split.sort_by(|x, y| x.0.total_cmp(&y.0));
+        split.push((1.0, edge.p1));
+
+        let param = |t: f64| {
+            if t >= 1.0 {
+                edge.t1
+            } else {
+                edge.t0 + t as f32 * (edge.t1 - edge.t0)
+            }
+        };
+
+        let mut prev = (0.0, edge.p0);
+        for &(t, point) in split.iter() {
+            if point == prev.1 {
+                continue;
+            }
+
+            let (v0, v1) = (vertex(prev.1), vertex(point));
+            let (t0, t1) = (
+                if prev.0 == 0.0 {
+                    edge.t0
+                } else {
+                    param(prev.0)
+                },
+                param(t),
+            );
+            let (key, sign) = if v0 < v1 {
+                ((v0, v1), 1)
+            } else {
+                ((v1, v0), -1)
+            };
+
+            let index = *piece_map.entry(key).or_insert_with(|| {
+                let (t0, t1) = if sign > 0 { (t0, t1) } else { (t1, t0) };
+                pieces.push(Piece {
+                    v0: key.0,
+                    v1: key.1,
+                    winding: [0, 0],
+                    curve: edge.curve,
+                    t0,
+                    t1,
+                });
+                pieces.len() - 1
+            });
+            pieces[index].winding[edge.operand] += sign;
+
+            prev = (t, point);
+        }
+    }
+
+    // Classify each piece by the winding numbers of both operands on either side of it, keeping
+    // those which separate the inside of the result from the outside. The winding numbers are
+    // found by casting a ray from the midpoint of each piece along the axis most transverse to
+    // it. A ray in the y direction is handled by swapping coordinates, which reverses
+    // orientation.
+    let transverse = |piece: &Piece| {
+        let dir = vertices[piece.v1] - vertices[piece.v0];
+        dir.x.abs() > dir.y.abs()
+    };
+    let active: Vec<usize> =
+        (0..pieces.len()).filter(|&index| pieces[index].winding != [0, 0]).collect();
+    let mut windings = vec![[0, 0]; pieces.len()];
+    for vertical in [false, true] {
+        let swap = |point: Vec2| {
+            if vertical {
+                Vec2::new(point.y, point.x)
+            } else {
+                point
+            }
+        };
+        let orientation = if vertical { -1 } else { 1 };
+        let mid = |piece: &Piece| swap(0.5 * (vertices[piece.v0] + vertices[piece.v1]));
+        let extent = |piece: &Piece| {
+            let (y0, y1) = (swap(vertices[piece.v0]).y, swap(vertices[piece.v1]).y);
+            (y0.min(y1), y0.max(y1))
+        };
+
+        // Sweep the rays in order of increasing y, keeping the pieces whose y extent spans the
+        // current ray, so that each ray is only tested against those.
+        let mut rays: Vec<usize> = active
+            .iter()
+            .copied()
+            .filter(|&index| transverse(&pieces[index]) == vertical)
+            .collect();
+        rays.sort_by(|&i, &j| mid(&pieces[i]).y.total_cmp(&mid(&pieces[j]).y));
+        let mut order = active.clone();
+        order.sort_by(|&i, &j| extent(&pieces[i]).0.total_cmp(&extent(&pieces[j]).0));
+
+        let mut next = 0;
+        let mut spanning = Vec::new();
+        for &index in &rays {
+            let point = mid(&pieces[index]);
+            while next < order.len() && extent(&pieces[order[next]]).0 <= point.y {
+                spanning.push(order[next]);
+                next += 1;
+            }
+            spanning.retain(|&other| extent(&pieces[other]).1 >= point.y);
+
+            let winding = &mut windings[index];
+            for &other_index in &spanning {
+                if other_index == index {
+                    continue;
+                }
+
+                let other = &pieces[other_index];
+                let crossing = orientation
+                    * ray_crossing(point, swap(vertices[other.v0]), swap(vertices[other.v1]));
+                winding[0] += crossing * other.winding[0];
+                winding[1] += crossing * other.winding[1];
+            }
+        }
+    }
+
+    let mut boundary = Vec::new();
+    for &index in &active {
+        let piece = &pieces[index];
+        let dir = vertices[piece.v1] - vertices[piece.v0];
+        let vertical = transverse(piece);
+        let orientation = if vertical { -1 } else { 1 };
+        let winding = windings[index];
+
+        // The point just behind the piece, as seen from the ray direction, also crosses the piece.
+        let along = if vertical { dir.x } else { dir.y };
+        let own = orientation * if along > 0.0 { 1 } else { -1 };
+        let inside_ahead = op.apply(
+            is_inside(winding[0], fill_rule),
+            is_inside(winding[1], fill_rule),
+        );
+        let inside_behind = op.apply(
+            is_inside(winding[0] + own * piece.winding[0], fill_rule),
+            is_inside(winding[1] + own * piece.winding[1], fill_rule),
+        );
+        if inside_ahead == inside_behind {
+            continue;
+        }
+
+        // Orient the piece so that the inside lies in the direction of (-dir.y, dir.x).
+        let ahead_is_left = if vertical { dir.x > 0.0 } else { dir.y < 0.0 };
+        let forward = ahead_is_left == inside_ahead;
+        boundary.push(if forward {
+            Boundary {
+                from: piece.v0,
+                to: piece.v1,
+                curve: piece.curve,
+                t_from: piece.t0,
+                t_to: piece.t1,
+            }
+        } else {
+            Boundary {
+                from: piece.v1,
+                to: piece.v0,
+                curve: piece.curve,
+                t_from: piece.t1,
+                t_to: piece.t0,
+            }
+        });
+    }
+
+    // Chain the boundary edges into closed loops.
+    let mut outgoing = vec![Vec::new(); vertices.len()];
+    for (index, edge) in boundary.iter().enumerate() {
+        outgoing[edge.from].push(index);
+    }
+
+    let mut path = Path::new();
+    let mut used = vec![false; boundary.len()];
+    let mut chain = Vec::new();
+    for start in 0..boundary.len() {
+        if used[start] {
+            continue;
+        }
+
+        chain.clear();
+        chain.push(start);
+        used[start] = true;
+        let mut current = boundary[start].to;
+        while current != boundary[start].from {
+            let Some(&next) = outgoing[current].iter().find(|&&next| !used[next]) else {
+                break;
+            };
+            chain.push(next);
+            used[next] = true;
+            current = boundary[next].to;
+        }
+
+        emit(&mut path, &mut chain, &boundary, &vertices, &curves);
+    }
+
+    path
+}
+
+/// Checks whether `next` continues the same original segment as `prev`, in the same direction.
+#[inline]
+fn continues(prev: &Boundary, next: &Boundary) -> bool {
+    next.curve == prev.curve
+        && next.t_from == prev.t_to
+        && (next.t_to > next.t_from) == (prev.t_to > prev.t_from)
+}
+
+/// Appends a loop of boundary edges to the path, merging consecutive pieces of the same original
+/// segment back into a single line or curve.
+fn emit(
+    path: &mut Path,
+    chain: &mut [usize],
+    boundary: &[Boundary],
+    vertices: &[Vec2],
+    curves: &[Segment],
+) {
+    // Start the loop at the beginning of a run, so that no run is split across the loop's start.
+    let len = chain.len();
+    if let Some(start) =
+        (0..len).find(|&i| !continues(&boundary[chain[(i + len - 1) % len]], &boundary[chain[i]]))
+    {
+        chain.rotate_left(start);
+    }
+
+    path.move_to(vertices[boundary[chain[0]].from].to_point());
+
+    let mut i = 0;
+    while i < len {
+        let first = &boundary[chain[i]];
+        let mut last = first;
+        let mut j = i + 1;
+        while j < len && continues(last, &boundary[chain[j]]) {
+            last = &boundary[chain[j]];
+            j += 1;
+        }
+
+        let end = vertices[last.to].to_point();
+        match subcurve(&curves[first.curve], first.t_from, last.t_to) {
+            Segment::Line(_) => {
+                path.line_to(end);
+            }
+            Segment::Quadratic(quadratic) => {
+                path.quadratic_to(quadratic.p1, end);
+            }
+            Segment::Cubic(cubic) => {
+                path.cubic_to(cubic.p1, cubic.p2, end);
+            }
+        }
+
+        i = j;
+    }
+
+    path.close();
+}
+
+impl Path {
+    /// Computes the union of two paths, i.e. the region inside of either path.
+    ///
+    /// The inside of each path is determined by `fill_rule`. Curves are flattened to within
+    /// `tolerance` to compute intersections, but curves which are not cut by the other path are
+    /// preserved in the result. The result consists of closed subpaths and can be filled with
+    /// either fill rule.
+    pub fn union(&self, other: &Path, fill_rule: FillRule, tolerance: f32) -> Path {
+        boolean(self, other, Op::Union, fill_rule, tolerance)
+    }
+
+    /// Computes the intersection of two paths, i.e. the region inside of both paths.
+    ///
+    /// See [`Path::union`] for the meaning of the parameters.
+    pub fn intersection(&self, other: &Path, fill_rule: FillRule, tolerance: f32) -> Path {
+        boolean(self, other, Op::Intersection, fill_rule, tolerance)
+    }
+
+    /// Computes the difference of two paths, i.e. the region inside of `self` but not `other`.
+    ///
+    /// See [`Path::union`] for the meaning of the parameters.
+    pub fn difference(&self, other: &Path, fill_rule: FillRule, tolerance: f32) -> Path {
+        boolean(self, other, Op::Difference, fill_rule, tolerance)
+    }
+
+    /// Computes the symmetric difference of two paths, i.e. the region inside of exactly one of
+    /// the paths.
+    ///
+    /// See [`Path::union`] for the meaning of the parameters.
+    pub fn xor(&self, other: &Path, fill_rule: FillRule, tolerance: f32) -> Path {
+        boolean(self, other, Op::Xor, fill_rule, tolerance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geom::Rect;
+    use crate::path::Verb;
+
+    fn check(path: &Path, inside: &[(f32, f32)], outside: &[(f32, f32)]) {
+        for fill_rule in [FillRule::NonZero, FillRule::EvenOdd] {
+            for &(x, y) in inside {
+                assert!(path.contains(Point::new(x, y), fill_rule), "({x}, {y})");
+            }
+            for &(x, y) in outside {
+                assert!(!path.contains(Point::new(x, y), fill_rule), "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn rects() {
+        let a = Path::rect(Point::new(0.0, 0.0), 2.0, 2.0);
+        let b = Path::rect(Point::new(1.0, 1.0), 2.0, 2.0);
+        let (only_a, both, only_b, neither) = ((0.5, 0.5), (1.5, 1.5), (2.5, 2.5), (2.5, 0.5));
+
+        let union = a.union(&b, FillRule::NonZero, 0.1);
+        check(&union, &[only_a, both, only_b], &[neither]);
+        assert_eq!(union.verbs.len(), 10);
+
+        let intersection = a.intersection(&b, FillRule::NonZero, 0.1);
+        check(&intersection, &[both], &[only_a, only_b, neither]);
+        assert_eq!(
+            intersection.bounds(),
+            Some(Rect::new(Point::new(1.0, 1.0), Point::new(2.0, 2.0)))
+        );
+
+        let difference = a.difference(&b, FillRule::NonZero, 0.1);
+        check(&difference, &[only_a], &[both, only_b, neither]);
+
+        let xor = a.xor(&b, FillRule::NonZero, 0.1);
+        check(&xor, &[only_a, only_b], &[both, neither]);
+    }
+
+    #[test]
+    fn shared_edge() {
+        let a = Path::rect(Point::new(0.0, 0.0), 1.0, 1.0);
+        let b = Path::rect(Point::new(1.0, 0.0), 1.0, 1.0);
+
+        let union = a.union(&b, FillRule::NonZero, 0.1);
+        check(&union, &[(0.5, 0.5), (1.0, 0.5), (1.5, 0.5)], &[(2.5, 0.5)]);
+        assert_eq!(union.verbs.iter().filter(|&&v| v == Verb::Move).count(), 1);
+
+        let intersection = a.intersection(&b, FillRule::NonZero, 0.1);
+        assert!(intersection.is_empty());
+    }
+
+    #[test]
+    fn circles() {
+        let outer = Path::circle(Point::new(0.0, 0.0), 10.0);
+        let inner = Path::circle(Point::new(0.0, 0.0), 5.0);
+
+        // Cutting a hole preserves both circles as curves.
+        let ring = outer.difference(&inner, FillRule::NonZero, 0.01);
+        check(
+            &ring,
+            &[(7.5, 0.0), (0.0, -9.9)],
+            &[(0.0, 0.0), (10.5, 0.0)],
+        );
+        assert_eq!(ring.verbs.iter().filter(|&&v| v == Verb::Cubic).count(), 8);
+
+        let offset = Path::circle(Point::new(10.0, 0.0), 5.0);
+        let union = outer.union(&offset, FillRule::NonZero, 0.01);
+        check(
+            &union,
+            &[(0.0, 0.0), (14.0, 0.0)],
+            &[(10.0, 6.0), (16.0, 0.0)],
+        );
+        let bounds = union.bounds().unwrap();
+        assert!((bounds.max.x - 15.0).abs() < 0.01 && (bounds.min.y + 10.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn fill_rules() {
+        // A pentagram, whose center has a winding number of 2.
+        let points: Vec<Point> = (0..5)
+            .map(|i| {
+                let angle = (i * 2) as f32 * 0.4 * std::f32::consts::PI;
+                Point::new(10.0 * angle.sin(), -10.0 * angle.cos())
+            })
+            .collect();
+        let star = Path::polygon(&points);
+
+        let non_zero = star.union(&Path::new(), FillRule::NonZero, 0.1);
+        check(&non_zero, &[(0.0, 0.0), (0.0, -7.0)], &[(0.0, 9.0)]);
+
+        let even_odd = star.union(&Path::new(), FillRule::EvenOdd, 0.1);
+        check(&even_odd, &[(0.0, -7.0)], &[(0.0, 0.0), (0.0, 9.0)]);
+    }
+}
@@ -0,0 +1,414 @@
+use std::error;
+use std::fmt::{self, Write};
+
+use crate::geom::Point;
+use crate::path::{Path, Verb};
+
+/// An error encountered while parsing SVG path data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathDataError {
+    offset: usize,
+}
+
+impl PathDataError {
+    /// Gets the byte offset in the input at which the error was encountered.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl error::Error for PathDataError {}
+
+impl fmt::Display for PathDataError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "invalid path data at offset {}", self.offset)
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    #[inline]
+    fn error(&self) -> PathDataError {
+        PathDataError { offset: self.pos }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    #[inline]
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r' | b'\x0C') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    #[inline]
+    fn skip_separator(&mut self) {
+        self.skip_whitespace();
+        if self.peek() == Some(b',') {
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+    }
+
+    #[inline]
+    fn skip_digits(&mut self) -> usize {
+        let start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    /// Returns true if the next token is the start of a number.
+    #[inline]
+    fn at_number(&self) -> bool {
+        matches!(self.peek(), Some(b'0'..=b'9' | b'.' | b'-' | b'+'))
+    }
+
+    fn number(&mut self) -> Result<f32, PathDataError> {
+        self.skip_whitespace();
+
+        let start = self.pos;
+        if let Some(b'-' | b'+') = self.peek() {
+            self.pos += 1;
+        }
+
+        let mut digits = self.skip_digits();
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            digits += self.skip_digits();
+        }
+        if digits == 0 {
+            self.pos = start;
+            return Err(self.error());
+        }
+
+        if let Some(b'e' | b'E') = self.peek() {
+            let mantissa_end = self.pos;
+            self.pos += 1;
+            if let Some(b'-' | b'+') = self.peek() {
+                self.pos += 1;
+            }
+            if self.skip_digits() == 0 {
+                self.pos = mantissa_end;
+            }
+        }
+
+        // The slice only contains ASCII characters accepted above.
+        let text = std::str::from_utf8(&self.input[start..self.pos]).unwrap();
+        let value = text.parse::<f32>().map_err(|_| PathDataError { offset: start })?;
+
+        self.skip_separator();
+
+        Ok(value)
+    }
+
+    fn point(&mut self) -> Result<Point, PathDataError> {
+        let x = self.number()?;
+        let y = self.number()?;
+        Ok(Point::new(x, y))
+    }
+
+    fn flag(&mut self) -> Result<bool, PathDataError> {
+        self.skip_whitespace();
+
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(self.error()),
+        };
+        self.pos += 1;
+
+        self.skip_separator();
+
+        Ok(flag)
+    }
+}
+
+impl Path {
+    /// Parses SVG path data, as found in the `d` attribute of an SVG `<path>` element.
+    ///
+    /// All commands are supported, including relative commands, horizontal and vertical lines,
+    /// smooth curves, and elliptical arcs (which are converted to cubic Béziers).
+    pub fn from_svg_data(data: &str) -> Result<Path, PathDataError> {
+        let mut parser = Parser {
+            input: data.as_bytes(),
+            pos: 0,
+        };
+
+        let mut path = Path::new();
+
+        let mut command: Option<u8> = None;
+        let mut start = Point::new(0.0, 0.0);
+        let mut current = Point::new(0.0, 0.0);
+        let mut prev_cubic: Option<Point> = None;
+        let mut prev_quadratic: Option<Point> = None;
+
+        loop {
+            parser.skip_whitespace();
+            let Some(next) = parser.peek() else {
+                break;
+            };
+
+            let command_pos = parser.pos;
+            if next.is_ascii_alphabetic() {
+                command = Some(next);
+                parser.pos += 1;
+                parser.skip_whitespace();
+            } else if command.is_none() || !parser.at_number() {
+                return Err(parser.error());
+            }
+
+            let Some(letter) = command else {
+                return Err(parser.error());
+            };
+
+            let relative = letter.is_ascii_lowercase();
+            let origin = if relative {
+                current
+            } else {
+                Point::new(0.0, 0.0)
+            };
+
+            if path.is_empty() && !letter.eq_ignore_ascii_case(&b'M') {
+                return Err(PathDataError {
+                    offset: command_pos,
+                });
+            }
+
+            let mut cubic = None;
+            let mut quadratic = None;
+            match letter.to_ascii_uppercase() {
+                b'M' => {
+                    current = origin + parser.point()?;
+                    start = current;
+                    path.move_to(current);
+
+                    // Subsequent coordinate pairs are treated as line commands.
+                    command = Some(if relative { b'l' } else { b'L' });
+                }
+                b'L' => {
+                    current = origin + parser.point()?;
+                    path.line_to(current);
+                }
+                b'H' => {
+                    current.x = origin.x + parser.number()?;
+                    path.line_to(current);
+                }
+                b'V' => {
+                    current.y = origin.y + parser.number()?;
+                    path.line_to(current);
+                }
+                b'C' => {
+                    let control1 = origin + parser.point()?;
+                    let control2 = origin + parser.point()?;
+                    current = origin + parser.point()?;
+                    path.cubic_to(control1, control2, current);
+                    cubic = Some(control2);
+                }
+                b'S' => {
+                    let control1 = match prev_cubic {
+                        Some(control) => 2.0 * current - control,
+                        None => current,
+                    };
+                    let control2 = origin + parser.point()?;
+                    current = origin + parser.point()?;
+                    path.cubic_to(control1, control2, current);
+                    cubic = Some(control2);
+                }
+                b'Q' => {
+                    let control = origin + parser.point()?;
+                    current = origin + parser.point()?;
+                    path.quadratic_to(control, current);
+                    quadratic = Some(control);
+                }
+                b'T' => {
+                    let control = match prev_quadratic {
+                        Some(control) => 2.0 * current - control,
+                        None => current,
+                    };
+                    current = origin + parser.point()?;
+                    path.quadratic_to(control, current);
+                    quadratic = Some(control);
+                }
+                b'A' => {
+                    let radii = parser.point()?;
+                    let rotation = parser.number()?.to_radians();
+                    let large_arc = parser.flag()?;
+                    let sweep = parser.flag()?;
+                    let end = origin + parser.point()?;
+                    path.arc_to(radii, rotation, large_arc, sweep, end);
+                    current = end;
+                }
+                b'Z' => {
+                    path.close();
+                    current = start;
+
+                    // Closepath takes no arguments and cannot be repeated implicitly.
+                    command = None;
+                }
+                _ => {
+                    return Err(PathDataError {
+                        offset: command_pos,
+                    });
+                }
+            }
+
+            prev_cubic = cubic;
+            prev_quadratic = quadratic;
+        }
+
+        Ok(path)
+    }
+
+    /// Serializes the path as SVG path data, using absolute commands.
+    pub fn to_svg_data(&self) -> String {
+        let mut data = String::new();
+
+        let mut points = self.points.iter();
+        for verb in &self.verbs {
+            if !data.is_empty() {
+                data.push(' ');
+            }
+
+            // Writing to a String cannot fail.
+            let _ = match *verb {
+                Verb::Move => {
+                    let p = points.next().unwrap();
+                    write!(data, "M{} {}", p.x, p.y)
+                }
+                Verb::Line => {
+                    let p = points.next().unwrap();
+                    write!(data, "L{} {}", p.x, p.y)
+                }
+                Verb::Quadratic => {
+                    let p1 = points.next().unwrap();
+                    let p2 = points.next().unwrap();
+                    write!(data, "Q{} {} {} {}", p1.x, p1.y, p2.x, p2.y)
+                }
+                Verb::Cubic => {
+                    let p1 = points.next().unwrap();
+                    let p2 = points.next().unwrap();
+                    let p3 = points.next().unwrap();
+                    write!(
+                        data,
+                        "C{} {} {} {} {} {}",
+                        p1.x, p1.y, p2.x, p2.y, p3.x, p3.y
+                    )
+                }
+                Verb::Close => write!(data, "Z"),
+            };
+        }
+
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let path = Path::from_svg_data("M10,20 l5-5 h10 V0 z m1 1 L2 2 3 3").unwrap();
+
+        let mut expected = Path::new();
+        expected.move_to(Point::new(10.0, 20.0));
+        expected.line_to(Point::new(15.0, 15.0));
+        expected.line_to(Point::new(25.0, 15.0));
+        expected.line_to(Point::new(25.0, 0.0));
+        expected.close();
+        expected.move_to(Point::new(11.0, 21.0));
+        expected.line_to(Point::new(2.0, 2.0));
+        expected.line_to(Point::new(3.0, 3.0));
+
+        assert_eq!(path, expected);
+    }
+
+    #[test]
+    fn parse_numbers() {
+        let path = Path::from_svg_data("M.5.5L-1e1-2E-1+3.,4").unwrap();
+
+        let mut expected = Path::new();
+        expected.move_to(Point::new(0.5, 0.5));
+        expected.line_to(Point::new(-10.0, -0.2));
+        expected.line_to(Point::new(3.0, 4.0));
+
+        assert_eq!(path, expected);
+    }
+
+    #[test]
+    fn parse_smooth() {
+        let path = Path::from_svg_data("M0 0 C0 1 1 1 1 0 s2 -1 2 0 Q3 1 4 0 T6 0").unwrap();
+
+        let mut expected = Path::new();
+        expected.move_to(Point::new(0.0, 0.0));
+        expected.cubic_to(
+            Point::new(0.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 0.0),
+        );
+        expected.cubic_to(
+            Point::new(1.0, -1.0),
+            Point::new(3.0, -1.0),
+            Point::new(3.0, 0.0),
+        );
+        expected.quadratic_to(Point::new(3.0, 1.0), Point::new(4.0, 0.0));
+        expected.quadratic_to(Point::new(5.0, -1.0), Point::new(6.0, 0.0));
+
+        assert_eq!(path, expected);
+    }
+
+    #[test]
+    fn parse_arc() {
+        let path = Path::from_svg_data("M0 0 a5 5 0 1010 0").unwrap();
+
+        // A half circle below the x-axis, split into two quarter arcs.
+        assert_eq!(path.verbs, [Verb::Move, Verb::Cubic, Verb::Cubic]);
+        assert_eq!(*path.points.last().unwrap(), Point::new(10.0, 0.0));
+        let middle = path.points[3];
+        assert!((middle.x - 5.0).abs() < 1e-4 && (middle.y - 5.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            Path::from_svg_data("L0 0"),
+            Err(PathDataError { offset: 0 })
+        );
+        assert_eq!(
+            Path::from_svg_data("M0 0 L1"),
+            Err(PathDataError { offset: 7 })
+        );
+        assert_eq!(
+            Path::from_svg_data("M0 0 Z 1 1"),
+            Err(PathDataError { offset: 7 })
+        );
+        assert_eq!(
+            Path::from_svg_data("M0 0 X"),
+            Err(PathDataError { offset: 5 })
+        );
+        assert_eq!(
+            Path::from_svg_data("M0 0 A1 1 0 2 0 1 1"),
+            Err(PathDataError { offset: 12 })
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut path = Path::rounded_rect(Point::new(1.5, 2.0), 10.0, 20.0, [1.0, 2.0, 3.0, 0.0]);
+        path.move_to(Point::new(0.0, 0.0));
+        path.quadratic_to(Point::new(1.0, 2.0), Point::new(-3.25, 4.0));
+
+        let data = path.to_svg_data();
+        assert_eq!(Path::from_svg_data(&data).unwrap(), path);
+    }
+}
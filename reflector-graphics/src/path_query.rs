@@ -0,0 +1,346 @@
+use crate::curve::{self, arc_length, inverse_arc_length, Curve, Segment};
+use crate::geom::{Point, Rect};
+use crate::path::{Command, FillRule, Path};
+
+/// Finds the distance from a point to the line segment between `p0` and `p1`.
+#[inline]
+fn line_distance(point: Point, p0: Point, p1: Point) -> f32 {
+    let dir = p1 - p0;
+    let len_squared = dir.dot(dir);
+    if len_squared == 0.0 {
+        return point.distance(p0);
+    }
+
+    let t = ((point - p0).dot(dir) / len_squared).clamp(0.0, 1.0);
+    point.distance(p0 + t * dir)
+}
+
+/// Computes the contribution of a segment to the winding number of a point, by counting signed
+/// crossings of the segment with a ray cast from the point in the positive x direction.
+fn segment_winding(segment: &Segment, point: Point) -> i32 {
+    let mut splits = [0.0; 4];
+    let mut count = 0;
+    splits[count] = 0.0;
+    count += 1;
+    segment.extrema(curve::y, &mut |t| {
+        splits[count] = t;
+        count += 1;
+    });
+    splits[1..count].sort_by(|a, b| a.total_cmp(b));
+    splits[count] = 1.0;
+    count += 1;
+
+    let mut winding = 0;
+    for piece in splits[..count].windows(2) {
+        let (mut t0, mut t1) = (piece[0], piece[1]);
+        let (y0, y1) = (segment.eval(t0).y, segment.eval(t1).y);
+
+        // Half-open intervals ensure that crossings at shared endpoints are counted once.
+        let direction = if y0 <= point.y && point.y < y1 {
+            1
+        } else if y1 <= point.y && point.y < y0 {
+            -1
+        } else {
+            continue;
+        };
+
+        // Each piece is monotonic in y, so the crossing can be found by bisection.
+        let increasing = y1 > y0;
+        for _ in 0..32 {
+            let mid = 0.5 * (t0 + t1);
+            if (segment.eval(mid).y < point.y) == increasing {
+                t0 = mid;
+            } else {
+                t1 = mid;
+            }
+        }
+
+        if segment.eval(0.5 * (t0 + t1)).x > point.x {
+            winding += direction;
+        }
+    }
+
+    winding
+}
+
+impl Path {
+    /// Computes the bounding box of all points of the path, including control points.
+    ///
+    /// This is cheaper to compute than [`Path::bounds`], but may be larger than the path itself.
+    /// Returns `None` if the path is empty.
+    pub fn control_bounds(&self) -> Option<Rect> {
+        let (&first, rest) = self.points.split_first()?;
+
+        let mut bounds = Rect::new(first, first);
+        for &point in rest {
+            bounds = bounds.include(point);
+        }
+
+        Some(bounds)
+    }
+
+    /// Computes the exact bounding box of the path. Returns `None` if the path is empty.
+    pub fn bounds(&self) -> Option<Rect> {
+        // Start with the endpoints of every command, which also covers lone move commands.
+        let mut bounds: Option<Rect> = None;
+        let mut include = |point: Point| {
+            bounds = Some(match bounds {
+                Some(bounds) => bounds.include(point),
+                None => Rect::new(point, point),
+            });
+        };
+
+        for command in self.commands() {
+            match command {
+                Command::Move(point)
+                | Command::Line(point)
+                | Command::Quadratic(_, point)
+                | Command::Cubic(_, _, point) => include(point),
+                Command::Close => {}
+            }
+        }
+
+        for segment in self.segments(false) {
+            segment.extrema(curve::x, &mut |t| include(segment.eval(t)));
+            segment.extrema(curve::y, &mut |t| include(segment.eval(t)));
+        }
+
+        bounds
+    }
+
+    /// Checks whether a point lies inside the path, as determined by `fill_rule`.
+    ///
+    /// Open subpaths are treated as if they were closed, as when filling.
+    pub fn contains(&self, point: Point, fill_rule: FillRule) -> bool {
+        let mut winding = 0;
+        for segment in self.segments(true) {
+            winding += segment_winding(&segment, point);
+        }
+
+        match fill_rule {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+
+    /// Checks whether a point lies within `0.5 * width` of the path, i.e. inside a stroke of the
+    /// given width with round joins and caps.
+    pub fn stroke_contains(&self, point: Point, width: f32) -> bool {
+        let half_width = 0.5 * width;
+        let tolerance = (0.01 * half_width).max(1e-3);
+
+        for segment in self.segments(false) {
+            if let Segment::Line(line) = segment {
+                if line_distance(point, line.p0, line.p1) <= half_width {
+                    return true;
+                }
+                continue;
+            }
+
+            // Skip curves whose control polygon is too far away to possibly be hit.
+            let (min, max) = match segment {
+                Segment::Quadratic(q) => (q.p0.min(q.p1).min(q.p2), q.p0.max(q.p1).max(q.p2)),
+                Segment::Cubic(c) => (
+                    c.p0.min(c.p1).min(c.p2).min(c.p3),
+                    c.p0.max(c.p1).max(c.p2).max(c.p3),
+                ),
+                Segment::Line(_) => unreachable!(),
+            };
+            let dilate = Point::new(half_width, half_width);
+            if !Rect::new(min - dilate, max + dilate).contains(point) {
+                continue;
+            }
+
+            let segments = segment.segments_for_tolerance(tolerance).clamp(1, 1000);
+            let dt = 1.0 / segments as f32;
+            let mut prev = segment.start();
+            for i in 1..=segments {
+                let next = if i == segments {
+                    segment.end()
+                } else {
+                    segment.eval(i as f32 * dt)
+                };
+                if line_distance(point, prev, next) <= half_width - tolerance {
+                    return true;
+                }
+                prev = next;
+            }
+        }
+
+        false
+    }
+
+    /// Computes the total arc length of the path.
+    ///
+    /// Closing segments of closed subpaths are included.
+    pub fn length(&self) -> f32 {
+        self.segments(false).map(|segment| arc_length(&segment, 0.0, 1.0)).sum()
+    }
+
+    /// Finds the point at the given arc length along the path, along with the unit tangent
+    /// vector of the path at that point.
+    ///
+    /// Distances outside of the range `[0, self.length()]` are clamped to it. Returns `None` if the
+    /// path has no segments.
+    pub fn point_and_tangent_at(&self, distance: f32) -> Option<(Point, Point)> {
+        let mut remaining = distance.max(0.0);
+
+        let mut last = None;
+        for segment in self.segments(false) {
+            let length = arc_length(&segment, 0.0, 1.0);
+            if remaining <= length {
+                let t = inverse_arc_length(&segment, remaining, length);
+                return Some((segment.eval(t), segment_tangent(&segment, t)));
+            }
+
+            remaining -= length;
+            last = Some(segment);
+        }
+
+        let last = last?;
+        Some((last.end(), segment_tangent(&last, 1.0)))
+    }
+}
+
+/// Finds the unit tangent of a segment, falling back to nearby parameter values and then the
+/// chord at degenerate points.
+fn segment_tangent(segment: &Segment, t: f32) -> Point {
+    for tangent in [
+        segment.derivative(t),
+        segment.derivative((t - 1e-3).max(0.0)),
+        segment.derivative((t + 1e-3).min(1.0)),
+        segment.end() - segment.start(),
+    ] {
+        let length = tangent.length();
+        if length > 1e-9 {
+            return (1.0 / length) * tangent;
+        }
+    }
+
+    Point::new(0.0, 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn commands() {
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.quadratic_to(Point::new(1.0, 0.0), Point::new(1.0, 1.0));
+        path.close();
+
+        let commands: Vec<_> = path.commands().collect();
+        assert_eq!(
+            commands,
+            [
+                Command::Move(Point::new(0.0, 0.0)),
+                Command::Quadratic(Point::new(1.0, 0.0), Point::new(1.0, 1.0)),
+                Command::Close,
+            ]
+        );
+
+        let mut copy = Path::new();
+        for command in &path {
+            copy.push(command);
+        }
+        assert_eq!(copy, path);
+    }
+
+    #[test]
+    fn bounds() {
+        let circle = Path::circle(Point::new(10.0, 20.0), 5.0);
+        let bounds = circle.bounds().unwrap();
+        assert!(approx(bounds.min.x, 5.0) && approx(bounds.min.y, 15.0));
+        assert!(approx(bounds.max.x, 15.0) && approx(bounds.max.y, 25.0));
+
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.cubic_to(
+            Point::new(0.0, 4.0),
+            Point::new(4.0, 4.0),
+            Point::new(4.0, 0.0),
+        );
+        assert_eq!(
+            path.control_bounds(),
+            Some(Rect::new(Point::new(0.0, 0.0), Point::new(4.0, 4.0)))
+        );
+        assert_eq!(
+            path.bounds(),
+            Some(Rect::new(Point::new(0.0, 0.0), Point::new(4.0, 3.0)))
+        );
+
+        assert_eq!(Path::new().bounds(), None);
+    }
+
+    #[test]
+    fn contains() {
+        let mut path = Path::circle(Point::new(0.0, 0.0), 10.0);
+        path.move_to(Point::new(-5.0, -5.0));
+        path.line_to(Point::new(5.0, -5.0));
+        path.line_to(Point::new(5.0, 5.0));
+        path.line_to(Point::new(-5.0, 5.0));
+
+        assert!(path.contains(Point::new(0.0, 0.0), FillRule::NonZero));
+        assert!(!path.contains(Point::new(0.0, 0.0), FillRule::EvenOdd));
+        assert!(path.contains(Point::new(8.0, 0.0), FillRule::EvenOdd));
+        assert!(path.contains(Point::new(0.0, -9.9), FillRule::NonZero));
+        assert!(!path.contains(Point::new(7.5, 7.5), FillRule::NonZero));
+        assert!(!path.contains(Point::new(20.0, 0.0), FillRule::NonZero));
+    }
+
+    #[test]
+    fn stroke_contains() {
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.line_to(Point::new(10.0, 0.0));
+        path.quadratic_to(Point::new(20.0, 0.0), Point::new(20.0, 10.0));
+
+        assert!(path.stroke_contains(Point::new(5.0, 0.9), 2.0));
+        assert!(!path.stroke_contains(Point::new(5.0, 1.1), 2.0));
+        assert!(path.stroke_contains(Point::new(20.5, 10.0), 2.0));
+        assert!(!path.stroke_contains(Point::new(21.5, 10.0), 2.0));
+        assert!(!path.stroke_contains(Point::new(10.0, 10.0), 2.0));
+    }
+
+    #[test]
+    fn length() {
+        let circle = Path::circle(Point::new(0.0, 0.0), 1.0);
+        assert!((circle.length() - 2.0 * std::f32::consts::PI).abs() < 1e-2);
+
+        let square = Path::rect(Point::new(0.0, 0.0), 2.0, 3.0);
+        assert!(approx(square.length(), 10.0));
+    }
+
+    #[test]
+    fn point_and_tangent_at() {
+        let mut path = Path::new();
+        path.move_to(Point::new(0.0, 0.0));
+        path.line_to(Point::new(10.0, 0.0));
+        path.line_to(Point::new(10.0, 10.0));
+
+        let (point, tangent) = path.point_and_tangent_at(15.0).unwrap();
+        assert!(approx(point.x, 10.0) && approx(point.y, 5.0));
+        assert!(approx(tangent.x, 0.0) && approx(tangent.y, 1.0));
+
+        let (point, _) = path.point_and_tangent_at(100.0).unwrap();
+        assert_eq!(point, Point::new(10.0, 10.0));
+
+        let mut curve = Path::new();
+        curve.move_to(Point::new(0.0, 0.0));
+        curve.cubic_to(
+            Point::new(1.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(3.0, 0.0),
+        );
+        let (point, tangent) = curve.point_and_tangent_at(1.5).unwrap();
+        assert!(approx(point.x, 1.5) && approx(tangent.x, 1.0));
+
+        assert_eq!(Path::new().point_and_tangent_at(0.0), None);
+    }
+}
@@ -1,5 +1,8 @@
 use std::mem;
 
+use crate::paint::BlendMode;
+use crate::path::FillRule;
+use crate::shader::{Shader, LANE_OFFSETS};
 use crate::simd::*;
 use crate::{geom::Point, Color};
 
@@ -313,7 +316,6 @@ impl Rasterizer {
 
             x += 1;
             x_offset = 0.0;
-            x_offset_next -= 1.0;
 
             y_offset = y_offset_next;
             y_offset_next += dydx;
@@ -352,38 +354,30 @@ impl Rasterizer {
         self.bitmasks[offset + bitmask_index_max] |= mask;
     }
 
-    pub fn finish(&mut self, color: Color, data: &mut [u32], stride: usize) {
+    pub fn finish<T: Target>(&mut self, fill_rule: FillRule, target: &mut T) {
         #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
         {
             #[cfg(target_feature = "avx2")]
-            return self.finish_inner::<Avx2>(color, data, stride);
+            return self.finish_inner::<Avx2, T>(fill_rule, target);
 
             #[cfg(all(not(target_feature = "avx2"), target_feature = "sse2"))]
-            return self.finish_inner::<Sse2>(color, data, stride);
+            return self.finish_inner::<Sse2, T>(fill_rule, target);
 
             #[cfg(not(any(target_feature = "avx2", target_feature = "sse2")))]
-            return self.finish_inner::<Scalar>(color, data, stride);
+            return self.finish_inner::<Scalar, T>(fill_rule, target);
         }
 
         #[cfg(target_arch = "aarch64")]
         {
             #[cfg(target_feature = "neon")]
-            return self.finish_inner::<Neon>(color, data, stride);
+            return self.finish_inner::<Neon, T>(fill_rule, target);
         }
 
         #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
-        self.finish_inner::<Scalar>(color, data, stride)
+        self.finish_inner::<Scalar, T>(fill_rule, target)
     }
 
-    fn finish_inner<A: Arch>(&mut self, color: Color, data: &mut [u32], stride: usize) {
-        let a_unit = A::f32::from(color.a() as f32 * (1.0 / 255.0));
-        let src = Pixels {
-            a: A::f32::from(color.a() as f32),
-            r: a_unit * A::f32::from(color.r() as f32),
-            g: a_unit * A::f32::from(color.g() as f32),
-            b: a_unit * A::f32::from(color.b() as f32),
-        };
-
+    fn finish_inner<A: Arch, T: Target>(&mut self, fill_rule: FillRule, target: &mut T) {
         for y in 0..self.height {
             let mut accum = 0.0;
             let mut coverage = 0.0;
@@ -392,10 +386,6 @@ impl Rasterizer {
             let coverage_end = coverage_start + self.width;
             let coverage_row = &mut self.coverage[coverage_start..coverage_end];
 
-            let pixels_start = y * stride;
-            let pixels_end = pixels_start + self.width;
-            let pixels_row = &mut data[pixels_start..pixels_end];
-
             let bitmasks_start = y * self.bitmasks_width;
             let bitmasks_end = bitmasks_start + self.bitmasks_width;
             let bitmasks_row = &mut self.bitmasks[bitmasks_start..bitmasks_end];
@@ -425,26 +415,8 @@ impl Rasterizer {
                 }
 
                 // Composite an interior span (or skip an empty span).
-                if next_x > x {
-                    if coverage > 254.5 / 255.0 && color.a() == 255 {
-                        pixels_row[x..next_x].fill(color.into());
-                    } else if coverage > 0.5 / 255.0 {
-                        let mut pixels_chunks =
-                            pixels_row[x..next_x].chunks_exact_mut(A::u32::LANES);
-
-                        for pixels_slice in &mut pixels_chunks {
-                            let mask = A::f32::from(coverage);
-                            let dst = Pixels::<A>::unpack(A::u32::load(pixels_slice));
-                            dst.blend(src, mask).pack().store(pixels_slice);
-                        }
-
-                        let pixels_remainder = pixels_chunks.into_remainder();
-                        if !pixels_remainder.is_empty() {
-                            let mask = A::f32::from(coverage);
-                            let dst = Pixels::unpack(A::u32::load_partial(pixels_remainder));
-                            dst.blend(src, mask).pack().store_partial(pixels_remainder);
-                        }
-                    }
+                if next_x > x && coverage > 0.5 / 255.0 {
+                    target.fill::<A>(x, y, next_x - x, coverage);
                 }
 
                 x = next_x;
@@ -477,37 +449,31 @@ impl Rasterizer {
                     let coverage_slice = &mut coverage_row[x..next_x];
                     let mut coverage_chunks = coverage_slice.chunks_exact_mut(A::f32::LANES);
 
-                    let pixels_slice = &mut pixels_row[x..next_x];
-                    let mut pixels_chunks = pixels_slice.chunks_exact_mut(A::u32::LANES);
-
-                    for (coverage_chunk, pixels_chunk) in
-                        (&mut coverage_chunks).zip(&mut pixels_chunks)
-                    {
+                    let mut chunk_x = x;
+                    for coverage_chunk in &mut coverage_chunks {
                         let deltas = A::f32::load(coverage_chunk);
                         let accums = A::f32::from(accum) + deltas.prefix_sum();
                         accum = accums.last();
-                        let mask = accums.abs().min(A::f32::from(1.0));
+                        let mask = fill_rule.coverage::<A>(accums);
                         coverage = mask.last();
 
                         coverage_chunk.fill(0.0);
 
-                        let dst = Pixels::unpack(A::u32::load(pixels_chunk));
-                        dst.blend(src, mask).pack().store(pixels_chunk);
+                        target.blend::<A>(chunk_x, y, A::f32::LANES, mask);
+                        chunk_x += A::f32::LANES;
                     }
 
                     let coverage_remainder = coverage_chunks.into_remainder();
-                    let pixels_remainder = pixels_chunks.into_remainder();
-                    if !pixels_remainder.is_empty() && !coverage_remainder.is_empty() {
+                    if !coverage_remainder.is_empty() {
                         let deltas = A::f32::load_partial(coverage_remainder);
                         let accums = A::f32::from(accum) + deltas.prefix_sum();
                         accum = accums.last();
-                        let mask = accums.abs().min(A::f32::from(1.0));
+                        let mask = fill_rule.coverage::<A>(accums);
                         coverage = mask.last();
 
                         coverage_remainder.fill(0.0);
 
-                        let dst = Pixels::unpack(A::u32::load_partial(pixels_remainder));
-                        dst.blend(src, mask).pack().store_partial(pixels_remainder);
+                        target.blend::<A>(chunk_x, y, coverage_remainder.len(), mask);
                     }
                 }
 
@@ -520,11 +486,351 @@ impl Rasterizer {
     }
 }
 
-struct Pixels<A: Arch> {
-    a: A::f32,
-    r: A::f32,
-    g: A::f32,
-    b: A::f32,
+impl FillRule {
+    /// Converts accumulated winding numbers to coverage values.
+    #[inline(always)]
+    fn coverage<A: Arch>(self, accums: A::f32) -> A::f32 {
+        match self {
+            FillRule::NonZero => accums.abs().min(A::f32::from(1.0)),
+            FillRule::EvenOdd => {
+                let one = A::f32::from(1.0);
+                let accums = accums.abs();
+                let wrapped = accums - A::f32::from(2.0) * (A::f32::from(0.5) * accums).floor();
+                one - (wrapped - one).abs()
+            }
+        }
+    }
+}
+
+/// Passes a coverage mask to a target. `mask` consists of `height` rows of `width` coverage values,
+/// with consecutive rows starting `stride` values apart.
+pub fn blit_mask<T: Target>(
+    mask: &[f32],
+    stride: usize,
+    width: usize,
+    height: usize,
+    target: &mut T,
+) {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        #[cfg(target_feature = "avx2")]
+        return blit_mask_inner::<Avx2, T>(mask, stride, width, height, target);
+
+        #[cfg(all(not(target_feature = "avx2"), target_feature = "sse2"))]
+        return blit_mask_inner::<Sse2, T>(mask, stride, width, height, target);
+
+        #[cfg(not(any(target_feature = "avx2", target_feature = "sse2")))]
+        return blit_mask_inner::<Scalar, T>(mask, stride, width, height, target);
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        #[cfg(target_feature = "neon")]
+        return blit_mask_inner::<Neon, T>(mask, stride, width, height, target);
+    }
+
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    blit_mask_inner::<Scalar, T>(mask, stride, width, height, target)
+}
+
+fn blit_mask_inner<A: Arch, T: Target>(
+    mask: &[f32],
+    stride: usize,
+    width: usize,
+    height: usize,
+    target: &mut T,
+) {
+    for y in 0..height {
+        let row = &mask[y * stride..y * stride + width];
+
+        let mut x = 0;
+        for chunk in row.chunks(A::f32::LANES) {
+            // Skip empty chunks, and use the faster fill path for fully covered ones.
+            if chunk.iter().all(|&coverage| coverage > 254.5 / 255.0) {
+                target.fill::<A>(x, y, chunk.len(), 1.0);
+            } else if chunk.iter().any(|&coverage| coverage > 0.5 / 255.0) {
+                target.blend::<A>(x, y, chunk.len(), load::<A::f32>(chunk));
+            }
+
+            x += chunk.len();
+        }
+    }
+}
+
+/// A destination for the coverage values produced by the rasterizer.
+///
+/// Coordinates passed to a `Target` are relative to the origin of the rasterizer.
+pub trait Target {
+    /// Handles a span of `len` pixels which all share the same coverage value.
+    fn fill<A: Arch>(&mut self, x: usize, y: usize, len: usize, coverage: f32);
+
+    /// Handles up to `A::f32::LANES` pixels with individual coverage values.
+    fn blend<A: Arch>(&mut self, x: usize, y: usize, len: usize, mask: A::f32);
+}
+
+/// Composites a [`Shader`] onto a pixel buffer, optionally restricted by a clip mask with the same
+/// dimensions and stride.
+pub struct Composite<'a, 's> {
+    pub shader: &'a Shader<'s>,
+    pub data: &'a mut [u32],
+    pub clip: Option<&'a [f32]>,
+    pub stride: usize,
+    pub blend_mode: BlendMode,
+}
+
+impl<'a, 's> Composite<'a, 's> {
+    #[inline(always)]
+    fn composite<A: Arch>(&mut self, x: usize, y: usize, len: usize, mask: A::f32) {
+        let start = y * self.stride + x;
+        let pixels = &mut self.data[start..start + len];
+
+        let mut mask = mask;
+        if let Some(clip) = self.clip {
+            mask = mask * load::<A::f32>(&clip[start..start + len]);
+        }
+
+        let x_coords = A::f32::from(x as f32) + A::f32::load(&LANE_OFFSETS);
+        let src = self.shader.eval::<A>(x_coords, A::f32::from(y as f32));
+
+        let dst = Pixels::<A>::unpack(load::<A::u32>(pixels));
+        store(dst.blend(src, mask, self.blend_mode).pack(), pixels);
+    }
+}
+
+impl<'a, 's> Target for Composite<'a, 's> {
+    #[inline(always)]
+    fn fill<A: Arch>(&mut self, x: usize, y: usize, len: usize, coverage: f32) {
+        let end = x + len;
+
+        let replace = match self.blend_mode {
+            BlendMode::Src => true,
+            BlendMode::SrcOver => self.shader.is_opaque(),
+            _ => false,
+        };
+
+        if coverage > 254.5 / 255.0 && self.clip.is_none() && replace {
+            let start = y * self.stride + x;
+            if let Shader::Solid(color) = *self.shader {
+                self.data[start..start + len].fill(crate::image::premultiply(color));
+            } else {
+                let mut chunk_x = x;
+                while chunk_x < end {
+                    let chunk_len = (end - chunk_x).min(A::u32::LANES);
+                    let chunk_start = y * self.stride + chunk_x;
+                    let x_coords = A::f32::from(chunk_x as f32) + A::f32::load(&LANE_OFFSETS);
+                    let src = self.shader.eval::<A>(x_coords, A::f32::from(y as f32));
+                    store(
+                        src.pack(),
+                        &mut self.data[chunk_start..chunk_start + chunk_len],
+                    );
+                    chunk_x += chunk_len;
+                }
+            }
+            return;
+        }
+
+        let mut chunk_x = x;
+        while chunk_x < end {
+            let chunk_len = (end - chunk_x).min(A::u32::LANES);
+            self.composite::<A>(chunk_x, y, chunk_len, A::f32::from(coverage));
+            chunk_x += chunk_len;
+        }
+    }
+
+    #[inline(always)]
+    fn blend<A: Arch>(&mut self, x: usize, y: usize, len: usize, mask: A::f32) {
+        self.composite::<A>(x, y, len, mask);
+    }
+}
+
+/// Writes coverage values into a mask buffer, multiplied by the values of an optional parent
+/// mask with the same dimensions and stride.
+pub struct Mask<'a> {
+    pub data: &'a mut [f32],
+    pub parent: Option<&'a [f32]>,
+    pub stride: usize,
+}
+
+impl<'a> Mask<'a> {
+    #[inline(always)]
+    fn write<A: Arch>(&mut self, x: usize, y: usize, len: usize, mask: A::f32) {
+        let start = y * self.stride + x;
+
+        let mut mask = mask;
+        if let Some(parent) = self.parent {
+            mask = mask * load::<A::f32>(&parent[start..start + len]);
+        }
+
+        store(mask, &mut self.data[start..start + len]);
+    }
+}
+
+impl<'a> Target for Mask<'a> {
+    #[inline(always)]
+    fn fill<A: Arch>(&mut self, x: usize, y: usize, len: usize, coverage: f32) {
+        if self.parent.is_none() {
+            let start = y * self.stride + x;
+            self.data[start..start + len].fill(coverage);
+            return;
+        }
+
+        let end = x + len;
+        let mut chunk_x = x;
+        while chunk_x < end {
+            let chunk_len = (end - chunk_x).min(A::f32::LANES);
+            self.write::<A>(chunk_x, y, chunk_len, A::f32::from(coverage));
+            chunk_x += chunk_len;
+        }
+    }
+
+    #[inline(always)]
+    fn blend<A: Arch>(&mut self, x: usize, y: usize, len: usize, mask: A::f32) {
+        self.write::<A>(x, y, len, mask);
+    }
+}
+
+/// Composites a buffer of premultiplied pixels onto another with a constant opacity, optionally
+/// restricted by a clip mask. All three buffers share the same stride.
+pub struct CompositeLayer<'a> {
+    pub src: &'a [u32],
+    pub dst: &'a mut [u32],
+    pub clip: Option<&'a [f32]>,
+    pub stride: usize,
+    pub width: usize,
+    pub height: usize,
+    pub opacity: f32,
+    pub blend_mode: BlendMode,
+}
+
+impl<'a> CompositeLayer<'a> {
+    pub fn finish(&mut self) {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            #[cfg(target_feature = "avx2")]
+            return self.finish_inner::<Avx2>();
+
+            #[cfg(all(not(target_feature = "avx2"), target_feature = "sse2"))]
+            return self.finish_inner::<Sse2>();
+
+            #[cfg(not(any(target_feature = "avx2", target_feature = "sse2")))]
+            return self.finish_inner::<Scalar>();
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        {
+            #[cfg(target_feature = "neon")]
+            return self.finish_inner::<Neon>();
+        }
+
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+        self.finish_inner::<Scalar>()
+    }
+
+    fn finish_inner<A: Arch>(&mut self) {
+        for y in 0..self.height {
+            let row_start = y * self.stride;
+            let row_end = row_start + self.width;
+
+            let mut x = row_start;
+            while x < row_end {
+                let end = (x + A::u32::LANES).min(row_end);
+
+                let mut mask = A::f32::from(self.opacity);
+                if let Some(clip) = self.clip {
+                    mask = mask * load::<A::f32>(&clip[x..end]);
+                }
+
+                let src = Pixels::<A>::unpack(load::<A::u32>(&self.src[x..end]));
+                let dst = Pixels::<A>::unpack(load::<A::u32>(&self.dst[x..end]));
+                store(
+                    dst.blend(src, mask, self.blend_mode).pack(),
+                    &mut self.dst[x..end],
+                );
+
+                x = end;
+            }
+        }
+    }
+}
+
+/// Blends a single premultiplied color channel, with all values in the range `[0, 1]`.
+#[inline(always)]
+fn blend_channel<A: Arch>(
+    blend_mode: BlendMode,
+    s: A::f32,
+    d: A::f32,
+    sa: A::f32,
+    da: A::f32,
+) -> A::f32 {
+    let zero = A::f32::from(0.0);
+    let one = A::f32::from(1.0);
+    let two = A::f32::from(2.0);
+    let epsilon = A::f32::from(1.0 / 65536.0);
+
+    let inv_sa = one - sa;
+    let inv_da = one - da;
+    let sa_da = sa * da;
+
+    match blend_mode {
+        BlendMode::Clear => zero,
+        BlendMode::Src => s,
+        BlendMode::SrcOver => s + d * inv_sa,
+        BlendMode::DstOver => s * inv_da + d,
+        BlendMode::SrcIn => s * da,
+        BlendMode::DstOut => d * inv_sa,
+        BlendMode::Xor => s * inv_da + d * inv_sa,
+        BlendMode::Multiply => s * inv_da + d * inv_sa + s * d,
+        BlendMode::Screen => s + d - s * d,
+        BlendMode::Overlay => {
+            let low = two * s * d;
+            let high = sa_da - two * (da - d) * (sa - s);
+            s * inv_da + d * inv_sa + (two * d).select_lt(da + epsilon, low, high)
+        }
+        BlendMode::Darken => s + d - (s * da).max(d * sa),
+        BlendMode::Lighten => s + d - (s * da).min(d * sa),
+        BlendMode::ColorDodge => {
+            let dodge = sa_da.min(d * sa * sa / (sa - s).max(epsilon));
+            let term = s.select_lt(sa, dodge, sa_da);
+            let term = d.select_lt(epsilon, zero, term);
+            s * inv_da + d * inv_sa + term
+        }
+        BlendMode::ColorBurn => {
+            let burn = sa_da - sa_da.min((da - d) * sa * sa / s.max(epsilon));
+            let term = s.select_lt(epsilon, zero, burn);
+            let term = d.select_lt(da, term, sa_da);
+            s * inv_da + d * inv_sa + term
+        }
+        BlendMode::Difference => s + d - two * (s * da).min(d * sa),
+        BlendMode::Additive => (s + d).min(one),
+    }
+}
+
+/// Loads a full vector if the slice is long enough, or a partial vector otherwise.
+#[inline(always)]
+fn load<S: Simd>(slice: &[S::Elem]) -> S {
+    if slice.len() >= S::LANES {
+        S::load(slice)
+    } else {
+        S::load_partial(slice)
+    }
+}
+
+/// Stores a full vector if the slice is long enough, or a partial vector otherwise.
+#[inline(always)]
+fn store<S: Simd>(vector: S, slice: &mut [S::Elem]) {
+    if slice.len() >= S::LANES {
+        vector.store(slice);
+    } else {
+        vector.store_partial(slice);
+    }
+}
+
+/// Premultiplied pixel values, with each channel in the range `[0, 255]`.
+pub struct Pixels<A: Arch> {
+    pub a: A::f32,
+    pub r: A::f32,
+    pub g: A::f32,
+    pub b: A::f32,
 }
 
 impl<A: Arch> Clone for Pixels<A> {
@@ -537,7 +843,18 @@ impl<A: Arch> Copy for Pixels<A> {}
 
 impl<A: Arch> Pixels<A> {
     #[inline]
-    fn unpack(data: A::u32) -> Self {
+    pub fn solid(color: Color) -> Self {
+        let a_unit = A::f32::from(color.a() as f32 * (1.0 / 255.0));
+        Pixels {
+            a: A::f32::from(color.a() as f32),
+            r: a_unit * A::f32::from(color.r() as f32),
+            g: a_unit * A::f32::from(color.g() as f32),
+            b: a_unit * A::f32::from(color.b() as f32),
+        }
+    }
+
+    #[inline]
+    pub fn unpack(data: A::u32) -> Self {
         Pixels {
             a: A::f32::from((data >> 24) & A::u32::from(0xFF)),
             r: A::f32::from((data >> 16) & A::u32::from(0xFF)),
@@ -547,7 +864,7 @@ impl<A: Arch> Pixels<A> {
     }
 
     #[inline]
-    fn pack(self) -> A::u32 {
+    pub fn pack(self) -> A::u32 {
         let a = A::u32::from(self.a);
         let r = A::u32::from(self.r);
         let g = A::u32::from(self.g);
@@ -557,7 +874,58 @@ impl<A: Arch> Pixels<A> {
     }
 
     #[inline]
-    fn blend(self, src: Self, mask: A::f32) -> Self {
+    fn blend(self, src: Self, mask: A::f32, blend_mode: BlendMode) -> Self {
+        if blend_mode == BlendMode::SrcOver {
+            return self.src_over(src, mask);
+        }
+
+        // Compute the fully covered result, then interpolate towards it from the destination.
+        let result = self.apply(src, blend_mode);
+
+        let zero = A::f32::from(0.0);
+        let max = A::f32::from(255.0);
+        let lerp = |d: A::f32, r: A::f32| (d + mask * (r - d)).max(zero).min(max);
+        Pixels {
+            a: lerp(self.a, result.a),
+            r: lerp(self.r, result.r),
+            g: lerp(self.g, result.g),
+            b: lerp(self.b, result.b),
+        }
+    }
+
+    #[inline]
+    fn apply(self, src: Self, blend_mode: BlendMode) -> Self {
+        let scale = A::f32::from(1.0 / 255.0);
+        let unscale = A::f32::from(255.0);
+
+        let sa = scale * src.a;
+        let da = scale * self.a;
+        let channel = |s: A::f32, d: A::f32| {
+            unscale * blend_channel::<A>(blend_mode, scale * s, scale * d, sa, da)
+        };
+
+        let a = match blend_mode {
+            BlendMode::Multiply
+            | BlendMode::Screen
+            | BlendMode::Overlay
+            | BlendMode::Darken
+            | BlendMode::Lighten
+            | BlendMode::ColorDodge
+            | BlendMode::ColorBurn
+            | BlendMode::Difference => unscale * (sa + da - sa * da),
+            _ => unscale * blend_channel::<A>(blend_mode, sa, da, sa, da),
+        };
+
+        Pixels {
+            a,
+            r: channel(src.r, self.r),
+            g: channel(src.g, self.g),
+            b: channel(src.b, self.b),
+        }
+    }
+
+    #[inline]
+    fn src_over(self, src: Self, mask: A::f32) -> Self {
         let inv_a = A::f32::from(1.0) - mask * A::f32::from(1.0 / 255.0) * src.a;
         Pixels {
             a: mask * src.a + inv_a * self.a,
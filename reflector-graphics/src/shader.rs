@@ -0,0 +1,534 @@
+use std::f32::consts::PI;
+
+use crate::color::Color;
+use crate::geom::{Affine, Point};
+use crate::image::{premultiply, Image};
+use crate::paint::{Extend, Filter, Gradient, GradientKind, GradientStop, Paint, Pattern};
+use crate::raster::Pixels;
+use crate::simd::*;
+
+const LUT_SIZE: usize = 256;
+
+/// Offsets of each lane from the first lane, used to compute per-lane pixel coordinates.
+pub const LANE_OFFSETS: [f32; 8] = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+
+/// A [`Paint`] prepared for evaluation at the pixels covered by a single draw call.
+pub enum Shader<'a> {
+    Solid(Color),
+    Gradient(Box<GradientShader>),
+    Image(ImageShader<'a>),
+}
+
+impl<'a> Shader<'a> {
+    /// Prepares a paint for drawing, given the transform from the paint's coordinate space to the
+    /// pixel grid of the rasterizer.
+    pub fn new(paint: Paint<'a>, transform: Affine) -> Shader<'a> {
+        let transparent = Shader::Solid(Color::rgba(0, 0, 0, 0));
+
+        match paint {
+            Paint::Solid(color) => Shader::Solid(color),
+            Paint::Gradient(gradient) => match GradientShader::new(gradient, transform) {
+                Some(shader) => Shader::Gradient(Box::new(shader)),
+                None => transparent,
+            },
+            Paint::Pattern(pattern) => match ImageShader::new(pattern, transform) {
+                Some(shader) => Shader::Image(shader),
+                None => transparent,
+            },
+        }
+    }
+
+    /// Returns true if every pixel produced by the shader is fully opaque.
+    #[inline]
+    pub fn is_opaque(&self) -> bool {
+        match self {
+            Shader::Solid(color) => color.a() == 255,
+            Shader::Gradient(gradient) => gradient.opaque,
+            Shader::Image(_) => false,
+        }
+    }
+
+    /// Evaluates the shader at the given pixel coordinates.
+    #[inline(always)]
+    pub fn eval<A: Arch>(&self, x: A::f32, y: A::f32) -> Pixels<A> {
+        match self {
+            Shader::Solid(color) => Pixels::solid(*color),
+            Shader::Gradient(gradient) => gradient.eval(x, y),
+            Shader::Image(image) => image.eval(x, y),
+        }
+    }
+}
+
+enum Kind {
+    Linear {
+        start: Point,
+        // The gradient vector scaled by the inverse of its squared length.
+        direction: Point,
+    },
+    Radial {
+        focus: Point,
+        // The vector from the center to the focus.
+        center_to_focus: Point,
+        // r^2 - |center_to_focus|^2
+        k: f32,
+    },
+    Sweep {
+        center: Point,
+        start_angle: f32,
+        direction: f32,
+        inv_range: f32,
+    },
+}
+
+pub struct GradientShader {
+    kind: Kind,
+    // Maps pixel coordinates to gradient space, including the offset to the pixel center.
+    transform: Affine,
+    extend: Extend,
+    opaque: bool,
+    lut: [u32; LUT_SIZE],
+}
+
+impl GradientShader {
+    fn new(gradient: &Gradient, transform: Affine) -> Option<GradientShader> {
+        let inverse = (transform * gradient.transform).inverse()?;
+        let transform = inverse * Affine::translate(0.5, 0.5);
+
+        let kind = match gradient.kind {
+            GradientKind::Linear { start, end } => {
+                let vector = end - start;
+                let length_squared = vector.dot(vector);
+                let direction = if length_squared > 0.0 {
+                    (1.0 / length_squared) * vector
+                } else {
+                    Point::new(0.0, 0.0)
+                };
+
+                Kind::Linear { start, direction }
+            }
+            GradientKind::Radial {
+                center,
+                radius,
+                focus,
+            } => {
+                let radius = radius.abs().max(f32::EPSILON);
+
+                // Keep the focal point strictly inside the circle so that every pixel maps to a
+                // single non-negative offset.
+                let mut center_to_focus = focus - center;
+                let max_distance = 0.99 * radius;
+                if center_to_focus.length() > max_distance {
+                    center_to_focus = max_distance * center_to_focus.normalized();
+                }
+
+                Kind::Radial {
+                    focus: center + center_to_focus,
+                    center_to_focus,
+                    k: radius * radius - center_to_focus.dot(center_to_focus),
+                }
+            }
+            GradientKind::Sweep {
+                center,
+                start_angle,
+                end_angle,
+            } => {
+                let range = end_angle - start_angle;
+                let direction = if range < 0.0 { -1.0 } else { 1.0 };
+
+                Kind::Sweep {
+                    center,
+                    start_angle,
+                    direction,
+                    inv_range: 1.0 / range.abs().max(f32::EPSILON),
+                }
+            }
+        };
+
+        let mut lut = [0; LUT_SIZE];
+        let opaque = build_lut(&gradient.stops, &mut lut);
+
+        Some(GradientShader {
+            kind,
+            transform,
+            extend: gradient.extend,
+            opaque,
+            lut,
+        })
+    }
+
+    #[inline(always)]
+    fn eval<A: Arch>(&self, x: A::f32, y: A::f32) -> Pixels<A> {
+        let [m0, m1, m2, m3, m4, m5] = self.transform.coeffs();
+        let gx = A::f32::from(m0) * x + A::f32::from(m1) * y + A::f32::from(m2);
+        let gy = A::f32::from(m3) * x + A::f32::from(m4) * y + A::f32::from(m5);
+
+        let t = match self.kind {
+            Kind::Linear { start, direction } => {
+                (gx - A::f32::from(start.x)) * A::f32::from(direction.x)
+                    + (gy - A::f32::from(start.y)) * A::f32::from(direction.y)
+            }
+            Kind::Radial {
+                focus,
+                center_to_focus,
+                k,
+            } => {
+                let dx = gx - A::f32::from(focus.x);
+                let dy = gy - A::f32::from(focus.y);
+                let b = A::f32::from(center_to_focus.x) * dx + A::f32::from(center_to_focus.y) * dy;
+                let dd = dx * dx + dy * dy;
+                (b + (b * b + A::f32::from(k) * dd).sqrt()) * A::f32::from(1.0 / k)
+            }
+            Kind::Sweep {
+                center,
+                start_angle,
+                direction,
+                inv_range,
+            } => {
+                let dx = gx - A::f32::from(center.x);
+                let dy = gy - A::f32::from(center.y);
+                let angle =
+                    (atan2::<A>(dy, dx) - A::f32::from(start_angle)) * A::f32::from(direction);
+                let turns = angle * A::f32::from(0.5 / PI);
+                let wrapped = angle - A::f32::from(2.0 * PI) * turns.floor();
+                wrapped * A::f32::from(inv_range)
+            }
+        };
+
+        let t = match self.extend {
+            Extend::Pad => t.max(A::f32::from(0.0)).min(A::f32::from(1.0)),
+            Extend::Repeat => t - t.floor(),
+            Extend::Reflect => {
+                let u = t - A::f32::from(2.0) * (A::f32::from(0.5) * t).floor();
+                A::f32::from(1.0) - (u - A::f32::from(1.0)).abs()
+            }
+        };
+
+        let max_index = (LUT_SIZE - 1) as f32;
+        let index = (t * A::f32::from(max_index) + A::f32::from(0.5))
+            .floor()
+            .max(A::f32::from(0.0))
+            .min(A::f32::from(max_index));
+
+        Pixels::unpack(gather::<A>(&self.lut, A::u32::from(index)))
+    }
+}
+
+pub struct ImageShader<'a> {
+    image: &'a Image,
+    // Maps pixel coordinates to image space, including the offset to the pixel center.
+    transform: Affine,
+    filter: Filter,
+    extend: Extend,
+}
+
+impl<'a> ImageShader<'a> {
+    fn new(pattern: Pattern<'a>, transform: Affine) -> Option<ImageShader<'a>> {
+        if pattern.image.width() == 0 || pattern.image.height() == 0 {
+            return None;
+        }
+
+        let inverse = (transform * pattern.transform).inverse()?;
+
+        Some(ImageShader {
+            image: pattern.image,
+            transform: inverse * Affine::translate(0.5, 0.5),
+            filter: pattern.filter,
+            extend: pattern.extend,
+        })
+    }
+
+    #[inline(always)]
+    fn eval<A: Arch>(&self, x: A::f32, y: A::f32) -> Pixels<A> {
+        let [m0, m1, m2, m3, m4, m5] = self.transform.coeffs();
+        let u = A::f32::from(m0) * x + A::f32::from(m1) * y + A::f32::from(m2);
+        let v = A::f32::from(m3) * x + A::f32::from(m4) * y + A::f32::from(m5);
+
+        let width = self.image.width() as f32;
+        let height = self.image.height() as f32;
+
+        match self.filter {
+            Filter::Nearest => {
+                let x = self.wrap::<A>(u.floor(), width);
+                let y = self.wrap::<A>(v.floor(), height);
+                Pixels::unpack(self.fetch::<A>(x, y))
+            }
+            Filter::Bilinear => {
+                let u = u - A::f32::from(0.5);
+                let v = v - A::f32::from(0.5);
+                let u0 = u.floor();
+                let v0 = v.floor();
+                let tu = u - u0;
+                let tv = v - v0;
+
+                let x0 = self.wrap::<A>(u0, width);
+                let x1 = self.wrap::<A>(u0 + A::f32::from(1.0), width);
+                let y0 = self.wrap::<A>(v0, height);
+                let y1 = self.wrap::<A>(v0 + A::f32::from(1.0), height);
+
+                let p00 = Pixels::<A>::unpack(self.fetch::<A>(x0, y0));
+                let p10 = Pixels::<A>::unpack(self.fetch::<A>(x1, y0));
+                let p01 = Pixels::<A>::unpack(self.fetch::<A>(x0, y1));
+                let p11 = Pixels::<A>::unpack(self.fetch::<A>(x1, y1));
+
+                let one = A::f32::from(1.0);
+                let w00 = (one - tu) * (one - tv);
+                let w10 = tu * (one - tv);
+                let w01 = (one - tu) * tv;
+                let w11 = tu * tv;
+
+                Pixels {
+                    a: w00 * p00.a + w10 * p10.a + w01 * p01.a + w11 * p11.a,
+                    r: w00 * p00.r + w10 * p10.r + w01 * p01.r + w11 * p11.r,
+                    g: w00 * p00.g + w10 * p10.g + w01 * p01.g + w11 * p11.g,
+                    b: w00 * p00.b + w10 * p10.b + w01 * p01.b + w11 * p11.b,
+                }
+            }
+        }
+    }
+
+    /// Maps integral texel coordinates into the range `[0, size - 1]` according to the extend
+    /// mode.
+    #[inline(always)]
+    fn wrap<A: Arch>(&self, coord: A::f32, size: f32) -> A::u32 {
+        let size_vec = A::f32::from(size);
+
+        let coord = match self.extend {
+            Extend::Pad => coord,
+            Extend::Repeat => coord - size_vec * (coord * A::f32::from(1.0 / size)).floor(),
+            Extend::Reflect => {
+                let period = A::f32::from(2.0 * size);
+                let m = coord - period * (coord * A::f32::from(0.5 / size)).floor();
+                m.select_lt(size_vec, m, period - A::f32::from(1.0) - m)
+            }
+        };
+
+        A::u32::from(coord.max(A::f32::from(0.0)).min(A::f32::from(size - 1.0)))
+    }
+
+    #[inline(always)]
+    fn fetch<A: Arch>(&self, x: A::u32, y: A::u32) -> A::u32 {
+        let width = self.image.width();
+        let data = self.image.data();
+
+        let mut result = A::u32::default();
+        for ((value, &x), &y) in
+            result.as_mut_slice().iter_mut().zip(x.as_slice()).zip(y.as_slice())
+        {
+            *value = data[y as usize * width + x as usize];
+        }
+        result
+    }
+}
+
+/// Looks up each lane of `indices` in `table`.
+#[inline(always)]
+pub fn gather<A: Arch>(table: &[u32], indices: A::u32) -> A::u32 {
+    let mut result = A::u32::default();
+    for (value, &index) in result.as_mut_slice().iter_mut().zip(indices.as_slice()) {
+        *value = table[index as usize];
+    }
+    result
+}
+
+/// Approximates `atan2(y, x)` to within about 1e-5 radians.
+#[inline(always)]
+fn atan2<A: Arch>(y: A::f32, x: A::f32) -> A::f32 {
+    let zero = A::f32::from(0.0);
+
+    let x_abs = x.abs();
+    let y_abs = y.abs();
+    let a = x_abs.min(y_abs) / x_abs.max(y_abs);
+    let a2 = a * a;
+
+    let mut r = A::f32::from(-0.011_721_2);
+    r = r * a2 + A::f32::from(0.052_653_32);
+    r = r * a2 + A::f32::from(-0.116_432_87);
+    r = r * a2 + A::f32::from(0.193_543_46);
+    r = r * a2 + A::f32::from(-0.332_623_47);
+    r = r * a2 + A::f32::from(0.999_977_26);
+    r = r * a;
+
+    let r = x_abs.select_lt(y_abs, A::f32::from(0.5 * PI) - r, r);
+    let r = x.select_lt(zero, A::f32::from(PI) - r, r);
+    y.select_lt(zero, -r, r)
+}
+
+/// Fills `lut` with premultiplied colors sampled evenly from the gradient stops. Returns true if
+/// all of the colors are opaque.
+fn build_lut(stops: &[GradientStop], lut: &mut [u32; LUT_SIZE]) -> bool {
+    let mut stops = stops.to_vec();
+    stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+
+    if stops.is_empty() {
+        lut.fill(0);
+        return false;
+    }
+
+    let mut opaque = true;
+    let mut next = 0;
+    for (i, entry) in lut.iter_mut().enumerate() {
+        let t = i as f32 / (LUT_SIZE - 1) as f32;
+        while next < stops.len() && stops[next].offset < t {
+            next += 1;
+        }
+
+        let color = if next == 0 {
+            stops[0].color
+        } else if next == stops.len() {
+            stops[stops.len() - 1].color
+        } else {
+            let prev = stops[next - 1];
+            let stop = stops[next];
+            let span = stop.offset - prev.offset;
+            let s = if span > 0.0 {
+                (t - prev.offset) / span
+            } else {
+                1.0
+            };
+            lerp_color(s, prev.color, stop.color)
+        };
+
+        opaque &= color.a() == 255;
+        *entry = premultiply(color);
+    }
+
+    opaque
+}
+
+#[inline]
+fn lerp_color(t: f32, a: Color, b: Color) -> Color {
+    let lerp = |a: u8, b: u8| (a as f32 + t * (b as f32 - a as f32) + 0.5) as u8;
+    Color::rgba(
+        lerp(a.r(), b.r()),
+        lerp(a.g(), b.g()),
+        lerp(a.b(), b.b()),
+        lerp(a.a(), b.a()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(shader: &Shader, x: f32, y: f32) -> [u8; 4] {
+        let pixels = shader.eval::<Scalar>(x.into(), y.into());
+        let color = Color::from(pixels.pack().last());
+        [color.r(), color.g(), color.b(), color.a()]
+    }
+
+    fn black_to_white(mut gradient: Gradient) -> Gradient {
+        gradient
+            .stop(0.0, Color::rgba(0, 0, 0, 255))
+            .stop(1.0, Color::rgba(255, 255, 255, 255));
+        gradient
+    }
+
+    #[test]
+    fn linear() {
+        let gradient = black_to_white(Gradient::linear(
+            Point::new(0.0, 0.0),
+            Point::new(100.0, 0.0),
+        ));
+        let shader = Shader::new(Paint::from(&gradient), Affine::id());
+
+        assert!(shader.is_opaque());
+        assert_eq!(eval(&shader, -10.0, 0.0), [0, 0, 0, 255]);
+        assert_eq!(eval(&shader, 200.0, 0.0), [255, 255, 255, 255]);
+
+        let middle = eval(&shader, 49.5, 0.0);
+        assert!((middle[0] as i32 - 128).abs() <= 1, "got {middle:?}");
+    }
+
+    #[test]
+    fn extend() {
+        let mut gradient = black_to_white(Gradient::linear(
+            Point::new(0.0, 0.0),
+            Point::new(100.0, 0.0),
+        ));
+
+        gradient.extend(Extend::Repeat);
+        let shader = Shader::new(Paint::from(&gradient), Affine::id());
+        assert!(eval(&shader, 109.5, 0.0)[0] < 32);
+
+        gradient.extend(Extend::Reflect);
+        let shader = Shader::new(Paint::from(&gradient), Affine::id());
+        assert!(eval(&shader, 109.5, 0.0)[0] > 224);
+    }
+
+    #[test]
+    fn radial() {
+        let gradient = black_to_white(Gradient::radial(Point::new(50.0, 50.0), 50.0));
+        let shader = Shader::new(Paint::from(&gradient), Affine::id());
+
+        assert_eq!(eval(&shader, 49.5, 49.5), [0, 0, 0, 255]);
+        assert_eq!(eval(&shader, 99.5, 49.5), [255, 255, 255, 255]);
+        assert_eq!(eval(&shader, 49.5, -0.5), [255, 255, 255, 255]);
+
+        let middle = eval(&shader, 74.5, 49.5);
+        assert!((middle[0] as i32 - 128).abs() <= 1, "got {middle:?}");
+    }
+
+    #[test]
+    fn sweep() {
+        let gradient = black_to_white(Gradient::sweep(Point::new(0.0, 0.0), 0.0, 2.0 * PI));
+        let shader = Shader::new(Paint::from(&gradient), Affine::translate(100.0, 100.0));
+
+        // A quarter turn from the start angle, towards positive y.
+        let quarter = eval(&shader, 99.5, 199.5);
+        assert!((quarter[0] as i32 - 64).abs() <= 1, "got {quarter:?}");
+
+        let half = eval(&shader, -0.5, 99.6);
+        assert!((half[0] as i32 - 128).abs() <= 1, "got {half:?}");
+    }
+
+    #[test]
+    fn premultiplied() {
+        let mut gradient = Gradient::linear(Point::new(0.0, 0.0), Point::new(1.0, 0.0));
+        gradient.stop(0.0, Color::rgba(255, 0, 0, 128));
+        let shader = Shader::new(Paint::from(&gradient), Affine::id());
+
+        assert!(!shader.is_opaque());
+        assert_eq!(eval(&shader, 0.0, 0.0), [128, 0, 0, 128]);
+    }
+
+    fn checkerboard() -> Image {
+        let white = premultiply(Color::rgba(255, 255, 255, 255));
+        let black = premultiply(Color::rgba(0, 0, 0, 255));
+        Image::from_data(2, 2, vec![white, black, black, white])
+    }
+
+    #[test]
+    fn image_nearest() {
+        let image = checkerboard();
+        let mut pattern = Pattern::new(&image);
+        pattern.filter(Filter::Nearest);
+        let shader = Shader::new(Paint::from(pattern), Affine::scale(10.0));
+
+        assert_eq!(eval(&shader, 5.0, 5.0), [255, 255, 255, 255]);
+        assert_eq!(eval(&shader, 15.0, 5.0), [0, 0, 0, 255]);
+        assert_eq!(eval(&shader, 25.0, 5.0), [255, 255, 255, 255]);
+
+        pattern.extend(Extend::Pad);
+        let shader = Shader::new(Paint::from(pattern), Affine::scale(10.0));
+        assert_eq!(eval(&shader, 25.0, 5.0), [0, 0, 0, 255]);
+        assert_eq!(eval(&shader, -25.0, 5.0), [255, 255, 255, 255]);
+
+        pattern.extend(Extend::Reflect);
+        let shader = Shader::new(Paint::from(pattern), Affine::scale(10.0));
+        assert_eq!(eval(&shader, 25.0, 5.0), [0, 0, 0, 255]);
+        assert_eq!(eval(&shader, 35.0, 5.0), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn image_bilinear() {
+        let image = checkerboard();
+        let mut pattern = Pattern::new(&image);
+        pattern.extend(Extend::Pad);
+        let shader = Shader::new(Paint::from(pattern), Affine::scale(10.0));
+
+        assert_eq!(eval(&shader, 4.5, 4.5), [255, 255, 255, 255]);
+
+        let middle = eval(&shader, 9.5, 4.5);
+        assert!((middle[0] as i32 - 128).abs() <= 1, "got {middle:?}");
+    }
+}
@@ -195,6 +195,21 @@ impl Float for f32x8 {
         unsafe { f32x8(_mm256_min_ps(self.0, rhs.0)) }
     }
 
+    #[inline(always)]
+    fn max(self, rhs: Self) -> Self {
+        unsafe { f32x8(_mm256_max_ps(self.0, rhs.0)) }
+    }
+
+    #[inline(always)]
+    fn floor(self) -> Self {
+        unsafe { f32x8(_mm256_floor_ps(self.0)) }
+    }
+
+    #[inline(always)]
+    fn sqrt(self) -> Self {
+        unsafe { f32x8(_mm256_sqrt_ps(self.0)) }
+    }
+
     #[inline(always)]
     fn prefix_sum(self) -> Self {
         #[inline]
@@ -215,6 +230,14 @@ impl Float for f32x8 {
 
         unsafe { inner(self) }
     }
+
+    #[inline(always)]
+    fn select_lt(self, rhs: Self, a: Self, b: Self) -> Self {
+        unsafe {
+            let mask = _mm256_cmp_ps::<_CMP_LT_OQ>(self.0, rhs.0);
+            f32x8(_mm256_blendv_ps(b.0, a.0, mask))
+        }
+    }
 }
 
 impl Add for f32x8 {
@@ -64,7 +64,12 @@ where
 {
     fn abs(self) -> Self;
     fn min(self, rhs: Self) -> Self;
+    fn max(self, rhs: Self) -> Self;
+    fn floor(self) -> Self;
+    fn sqrt(self) -> Self;
     fn prefix_sum(self) -> Self;
+    /// Selects lanes from `a` where `self < rhs` and lanes from `b` elsewhere.
+    fn select_lt(self, rhs: Self, a: Self, b: Self) -> Self;
 }
 
 #[allow(unused)]
@@ -93,6 +98,9 @@ mod tests {
             1.0,
             2.0,
             3.0,
+            -1.5,
+            0.5,
+            1.5,
             -f32::EPSILON,
             f32::EPSILON,
             f32::MIN,
@@ -162,6 +170,32 @@ mod tests {
                         "expected {a}.min({b}) == {correct}, got {c}"
                     );
                 }
+
+                let result = A::f32::load(chunk_a).max(A::f32::load(chunk_b));
+                for ((&a, &b), &c) in
+                    chunk_a.iter().zip(chunk_b.iter()).zip(result.as_slice().iter())
+                {
+                    let correct = if a > b { a } else { b };
+                    assert!(
+                        correct.to_bits() == c.to_bits(),
+                        "expected {a}.max({b}) == {correct}, got {c}"
+                    );
+                }
+
+                let result = A::f32::load(chunk_a).select_lt(
+                    A::f32::load(chunk_b),
+                    A::f32::from(1.0),
+                    A::f32::from(2.0),
+                );
+                for ((&a, &b), &c) in
+                    chunk_a.iter().zip(chunk_b.iter()).zip(result.as_slice().iter())
+                {
+                    let correct = if a < b { 1.0 } else { 2.0 };
+                    assert!(
+                        correct == c,
+                        "expected {a}.select_lt({b}, 1.0, 2.0) == {correct}, got {c}"
+                    );
+                }
             }
         }
 
@@ -191,6 +225,24 @@ mod tests {
                 );
             }
 
+            let result = A::f32::load(chunk).floor();
+            for (&a, &b) in chunk.iter().zip(result.as_slice().iter()) {
+                let correct = a.floor();
+                assert!(
+                    correct.to_bits() == b.to_bits() || (correct.is_nan() && b.is_nan()),
+                    "expected {a}.floor() == {correct}, got {b}"
+                );
+            }
+
+            let result = A::f32::load(chunk).sqrt();
+            for (&a, &b) in chunk.iter().zip(result.as_slice().iter()) {
+                let correct = a.sqrt();
+                assert!(
+                    correct.to_bits() == b.to_bits() || (correct.is_nan() && b.is_nan()),
+                    "expected {a}.sqrt() == {correct}, got {b}"
+                );
+            }
+
             // TODO: This test currently fails on SSE2 and AVX2 since we use a signed integer
             // conversion intrinsic (_mm(256)_cvtps_epi32).
             //
@@ -132,6 +132,21 @@ impl Float for f32x4 {
         unsafe { f32x4(vbslq_f32(vcltq_f32(self.0, rhs.0), self.0, rhs.0)) }
     }
 
+    #[inline(always)]
+    fn max(self, rhs: Self) -> Self {
+        unsafe { f32x4(vbslq_f32(vcgtq_f32(self.0, rhs.0), self.0, rhs.0)) }
+    }
+
+    #[inline(always)]
+    fn floor(self) -> Self {
+        unsafe { f32x4(vrndmq_f32(self.0)) }
+    }
+
+    #[inline(always)]
+    fn sqrt(self) -> Self {
+        unsafe { f32x4(vsqrtq_f32(self.0)) }
+    }
+
     #[inline(always)]
     fn prefix_sum(self) -> Self {
         #[inline]
@@ -145,6 +160,11 @@ impl Float for f32x4 {
 
         unsafe { inner(self) }
     }
+
+    #[inline(always)]
+    fn select_lt(self, rhs: Self, a: Self, b: Self) -> Self {
+        unsafe { f32x4(vbslq_f32(vcltq_f32(self.0, rhs.0), a.0, b.0)) }
+    }
 }
 
 impl Add for f32x4 {
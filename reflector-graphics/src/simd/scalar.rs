@@ -86,10 +86,34 @@ impl Float for f32x1 {
         f32x1(if self.0 < rhs.0 { self.0 } else { rhs.0 })
     }
 
+    #[inline]
+    fn max(self, rhs: Self) -> Self {
+        f32x1(if self.0 > rhs.0 { self.0 } else { rhs.0 })
+    }
+
+    #[inline]
+    fn floor(self) -> Self {
+        f32x1(self.0.floor())
+    }
+
+    #[inline]
+    fn sqrt(self) -> Self {
+        f32x1(self.0.sqrt())
+    }
+
     #[inline]
     fn prefix_sum(self) -> Self {
         self
     }
+
+    #[inline]
+    fn select_lt(self, rhs: Self, a: Self, b: Self) -> Self {
+        if self.0 < rhs.0 {
+            a
+        } else {
+            b
+        }
+    }
 }
 
 impl Add for f32x1 {
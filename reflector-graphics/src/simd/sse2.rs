@@ -139,6 +139,39 @@ impl Float for f32x4 {
         unsafe { f32x4(_mm_min_ps(self.0, rhs.0)) }
     }
 
+    #[inline(always)]
+    fn max(self, rhs: Self) -> Self {
+        unsafe { f32x4(_mm_max_ps(self.0, rhs.0)) }
+    }
+
+    #[inline(always)]
+    fn floor(self) -> Self {
+        #[inline]
+        #[target_feature(enable = "sse2")]
+        unsafe fn inner(value: f32x4) -> f32x4 {
+            // SSE2 has no rounding instruction, so truncate and then subtract 1 wherever the
+            // truncated value ended up above the original. Values with a magnitude of at least
+            // 2^23 (as well as infinities and NaN) are already integral and are passed through
+            // as-is, and the sign bit is carried over so that -0.0 stays -0.0.
+            let sign = _mm_set1_ps(-0.0);
+            let truncated = _mm_cvtepi32_ps(_mm_cvttps_epi32(value.0));
+            let adjust = _mm_and_ps(_mm_cmpgt_ps(truncated, value.0), _mm_set1_ps(1.0));
+            let floored = _mm_or_ps(_mm_sub_ps(truncated, adjust), _mm_and_ps(value.0, sign));
+            let small = _mm_cmplt_ps(_mm_andnot_ps(sign, value.0), _mm_set1_ps(8388608.0));
+            f32x4(_mm_or_ps(
+                _mm_and_ps(small, floored),
+                _mm_andnot_ps(small, value.0),
+            ))
+        }
+
+        unsafe { inner(self) }
+    }
+
+    #[inline(always)]
+    fn sqrt(self) -> Self {
+        unsafe { f32x4(_mm_sqrt_ps(self.0)) }
+    }
+
     #[inline(always)]
     fn prefix_sum(self) -> Self {
         #[inline]
@@ -152,6 +185,14 @@ impl Float for f32x4 {
 
         unsafe { inner(self) }
     }
+
+    #[inline(always)]
+    fn select_lt(self, rhs: Self, a: Self, b: Self) -> Self {
+        unsafe {
+            let mask = _mm_cmplt_ps(self.0, rhs.0);
+            f32x4(_mm_or_ps(_mm_and_ps(mask, a.0), _mm_andnot_ps(mask, b.0)))
+        }
+    }
 }
 
 impl Add for f32x4 {
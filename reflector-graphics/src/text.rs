@@ -1,18 +1,352 @@
-use std::iter::zip;
+use std::fs::{self, File};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::{error, fmt, io};
 
-use rustybuzz::{Face, UnicodeBuffer};
+use memmap2::Mmap;
+use rustybuzz::{ttf_parser, Face};
+
+use crate::color::Color;
+use crate::geom::{Point, Rect};
+use crate::paragraph::{Line, ParagraphStyle};
+
+static NEXT_FONT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// An error encountered while loading a font.
+#[derive(Debug)]
+pub enum FontError {
+    Io(io::Error),
+    /// The data is not a valid font, or the face index is out of range for a collection.
+    InvalidFont,
+}
+
+impl From<io::Error> for FontError {
+    fn from(err: io::Error) -> FontError {
+        FontError::Io(err)
+    }
+}
+
+impl error::Error for FontError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            FontError::Io(err) => Some(err),
+            FontError::InvalidFont => None,
+        }
+    }
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FontError::Io(err) => write!(fmt, "failed to read font: {}", err),
+            FontError::InvalidFont => write!(fmt, "invalid font data"),
+        }
+    }
+}
+
+#[derive(Clone)]
+enum FontData {
+    Static(&'static [u8]),
+    Shared(Arc<[u8]>),
+    Mapped(Arc<Mmap>),
+}
+
+impl FontData {
+    #[inline]
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            FontData::Static(data) => data,
+            FontData::Shared(data) => data,
+            FontData::Mapped(data) => data,
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Font {
+    // Declared before `data`, so that it is dropped first.
     pub(crate) face: Face<'static>,
+    data: FontData,
+    index: usize,
+    // Identifies the font in glyph caches. Clones share the same id.
+    pub(crate) id: usize,
+    // The CPAL palette used for color glyphs.
+    pub(crate) palette: u16,
 }
 
 impl Font {
     #[inline]
     pub fn from_bytes(data: &'static [u8], index: usize) -> Option<Font> {
-        let face = Face::from_slice(data, index as u32)?;
+        Font::from_data(FontData::Static(data), index)
+    }
 
-        Some(Self { face })
+    #[inline]
+    pub fn from_vec(data: Vec<u8>, index: usize) -> Option<Font> {
+        Font::from_data(FontData::Shared(data.into()), index)
+    }
+
+    /// Loads a font from shared data. Fonts loaded from different faces of the same collection
+    /// can share a single copy of the data.
+    #[inline]
+    pub fn from_arc(data: Arc<[u8]>, index: usize) -> Option<Font> {
+        Font::from_data(FontData::Shared(data), index)
+    }
+
+    /// Reads a font file into memory and loads a face from it.
+    pub fn from_file(path: impl AsRef<Path>, index: usize) -> Result<Font, FontError> {
+        let data = fs::read(path)?;
+        Font::from_vec(data, index).ok_or(FontError::InvalidFont)
+    }
+
+    /// Memory-maps a font file and loads a face from it. Only the parts of the file which are
+    /// used are read from disk.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while the font or any of its clones exist.
+    pub unsafe fn from_file_mapped(
+        path: impl AsRef<Path>,
+        index: usize,
+    ) -> Result<Font, FontError> {
+        let file = File::open(path)?;
+        let map = Mmap::map(&file)?;
+        Font::from_data(FontData::Mapped(Arc::new(map)), index).ok_or(FontError::InvalidFont)
+    }
+
+    fn from_data(data: FontData, index: usize) -> Option<Font> {
+        // SAFETY: The slice points to static, heap-allocated or memory-mapped data which is
+        // owned by `data` and does not move when `data` does. `data` is stored in the font
+        // alongside the face and is dropped after it.
+        let slice = unsafe { &*(data.as_slice() as *const [u8]) };
+        let face = Face::from_slice(slice, index as u32)?;
+        let id = NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed);
+
+        Some(Font {
+            face,
+            data,
+            index,
+            id,
+            palette: 0,
+        })
+    }
+
+    /// Counts the faces in font data: the number of fonts in a collection (such as a `.ttc`
+    /// file), 1 for a single font, or 0 if the data is not a font.
+    pub fn face_count(data: &[u8]) -> usize {
+        match ttf_parser::fonts_in_collection(data) {
+            Some(count) => count as usize,
+            None => Face::from_slice(data, 0).is_some() as usize,
+        }
+    }
+
+    /// Gets the data the font was loaded from, which contains every face of a collection.
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        self.data.as_slice()
+    }
+
+    /// Gets the index of the font's face within its data.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Constructs a copy of a variable font with the given values for its variation axes, such
+    /// as `(*b"wght", 700.0)`. Axes which are not listed keep their current values, and axes
+    /// which the font does not have are ignored.
+    ///
+    /// The variations are used both when shaping text and when rendering glyph outlines.
+    pub fn with_variations(&self, variations: &[([u8; 4], f32)]) -> Font {
+        let mut font = self.clone();
+        for &(tag, value) in variations {
+            font.face.set_variation(ttf_parser::Tag::from_bytes(&tag), value);
+        }
+        font.id = NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed);
+        font
+    }
+
+    /// Constructs a copy of the font which draws color glyphs using one of the font's color
+    /// palettes, numbered from 0 up to [`palette_count`](Font::palette_count). Palette 0, which is
+    /// the default, is used in place of palettes which the font does not have.
+    pub fn with_palette(&self, palette: u16) -> Font {
+        let mut font = self.clone();
+        font.palette = if palette < self.palette_count() {
+            palette
+        } else {
+            0
+        };
+        font
+    }
+
+    /// The number of color palettes the font provides for its `COLR` glyphs.
+    #[inline]
+    pub fn palette_count(&self) -> u16 {
+        self.face.color_palettes().map_or(0, |count| count.get())
+    }
+
+    /// Checks whether the font has color glyphs, defined either by layers in a `COLR` table or
+    /// by images in an `sbix` or `CBDT` table.
+    #[inline]
+    pub fn has_color_glyphs(&self) -> bool {
+        let tables = self.face.tables();
+        tables.colr.is_some() || tables.sbix.is_some() || tables.cbdt.is_some()
+    }
+
+    /// Checks whether the font has a glyph for the given character.
+    #[inline]
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.face.glyph_index(c).is_some()
+    }
+
+    /// Gets the glyph the font's character map assigns to a character.
+    #[inline]
+    pub fn glyph_index(&self, c: char) -> Option<u16> {
+        self.face.glyph_index(c).map(|glyph| glyph.0)
+    }
+
+    /// The number of font units per em, which is the scale of the font's design grid.
+    #[inline]
+    pub fn units_per_em(&self) -> u16 {
+        self.face.units_per_em() as u16
+    }
+
+    /// Gets the metrics of the font at a size in pixels.
+    ///
+    /// Metrics which the font does not provide are estimated: the cap height and x-height from
+    /// the heights of the glyphs for `H` and `x`, and the decorations from typical proportions.
+    pub fn metrics(&self, size: f32) -> FontMetrics {
+        let face = &self.face;
+        let scale = size / face.units_per_em() as f32;
+        let ascender = face.ascender() as f32;
+
+        // The height of the top of a glyph above the baseline.
+        let glyph_top = |c: char| {
+            let glyph = face.glyph_index(c)?;
+            let rect = face.outline_glyph(glyph, &mut NullBuilder)?;
+            Some(rect.y_max as f32)
+        };
+        let positive = |value: Option<i16>| value.filter(|&value| value > 0).map(f32::from);
+        let cap_height = positive(face.capital_height())
+            .or_else(|| glyph_top('H'))
+            .unwrap_or(0.7 * ascender);
+        let x_height =
+            positive(face.x_height()).or_else(|| glyph_top('x')).unwrap_or(0.5 * ascender);
+
+        let default_thickness = face.units_per_em() as f32 / 14.0;
+        let (underline_position, underline_thickness) = match face.underline_metrics() {
+            Some(m) => (m.position as f32, m.thickness as f32),
+            None => (-default_thickness, default_thickness),
+        };
+        let (strikeout_position, strikeout_thickness) = match face.strikeout_metrics() {
+            Some(m) => (m.position as f32, m.thickness as f32),
+            None => (0.3 * ascender, underline_thickness),
+        };
+
+        FontMetrics {
+            ascent: scale * ascender,
+            descent: -scale * face.descender() as f32,
+            line_gap: scale * face.line_gap() as f32,
+            cap_height: scale * cap_height,
+            x_height: scale * x_height,
+            underline_position: scale * underline_position,
+            underline_thickness: scale * underline_thickness,
+            strikeout_position: scale * strikeout_position,
+            strikeout_thickness: scale * strikeout_thickness,
+        }
+    }
+
+    /// Gets the horizontal advance of a glyph at a size in pixels, or 0 if the font has no such
+    /// glyph.
+    #[inline]
+    pub fn glyph_advance(&self, glyph: u16, size: f32) -> f32 {
+        let advance = self.face.glyph_hor_advance(ttf_parser::GlyphId(glyph)).unwrap_or(0);
+        advance as f32 * size / self.face.units_per_em() as f32
+    }
+
+    /// Gets the bounding box of the outline of a glyph at a size in pixels, relative to the
+    /// glyph's origin on the baseline with y pointing down. Returns `None` if the glyph has no
+    /// outline.
+    pub fn glyph_bounds(&self, glyph: u16, size: f32) -> Option<Rect> {
+        let rect = self.face.outline_glyph(ttf_parser::GlyphId(glyph), &mut NullBuilder)?;
+        let scale = size / self.face.units_per_em() as f32;
+        Some(Rect::new(
+            Point::new(scale * rect.x_min as f32, -scale * rect.y_max as f32),
+            Point::new(scale * rect.x_max as f32, -scale * rect.y_min as f32),
+        ))
+    }
+}
+
+/// An outline builder which discards the outline, used to compute the exact bounds of glyphs,
+/// which for variable fonts can differ from the bounds stored in the font.
+struct NullBuilder;
+
+impl ttf_parser::OutlineBuilder for NullBuilder {
+    fn move_to(&mut self, _x: f32, _y: f32) {}
+    fn line_to(&mut self, _x: f32, _y: f32) {}
+    fn quad_to(&mut self, _x1: f32, _y1: f32, _x: f32, _y: f32) {}
+    fn curve_to(&mut self, _x1: f32, _y1: f32, _x2: f32, _y2: f32, _x: f32, _y: f32) {}
+    fn close(&mut self) {}
+}
+
+/// Metrics of a font at a particular size, in pixels.
+///
+/// The positions of decorations are offsets of their top edges above the baseline, which are
+/// negative for decorations below it.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct FontMetrics {
+    /// The distance from the baseline to the top of the font's default line height.
+    pub ascent: f32,
+    /// The distance from the baseline to the bottom of the font's default line height.
+    pub descent: f32,
+    /// The space the font recommends between lines, in addition to the ascent and descent.
+    pub line_gap: f32,
+    /// The height of capital letters above the baseline.
+    pub cap_height: f32,
+    /// The height of lowercase letters such as `x` above the baseline.
+    pub x_height: f32,
+    pub underline_position: f32,
+    pub underline_thickness: f32,
+    pub strikeout_position: f32,
+    pub strikeout_thickness: f32,
+}
+
+/// An ordered list of fonts, used to find a font for characters not supported by a text style's
+/// primary font.
+#[derive(Clone, Default)]
+pub struct FontCollection {
+    fonts: Vec<Font>,
+}
+
+impl FontCollection {
+    #[inline]
+    pub fn new() -> FontCollection {
+        FontCollection { fonts: Vec::new() }
+    }
+
+    #[inline]
+    pub fn push(&mut self, font: Font) -> &mut Self {
+        self.fonts.push(font);
+        self
+    }
+
+    #[inline]
+    pub fn fonts(&self) -> &[Font] {
+        &self.fonts
+    }
+
+    /// Finds the first font in the collection with a glyph for the given character.
+    #[inline]
+    pub fn font_for_char(&self, c: char) -> Option<&Font> {
+        self.fonts.iter().find(|font| font.has_glyph(c))
+    }
+}
+
+impl From<Vec<Font>> for FontCollection {
+    #[inline]
+    fn from(fonts: Vec<Font>) -> FontCollection {
+        FontCollection { fonts }
     }
 }
 
@@ -23,50 +357,272 @@ pub struct Glyph {
     pub y: f32,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+pub struct TextDecoration {
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub overline: bool,
+}
+
+/// The attributes of a span of text.
+#[derive(Clone)]
+pub struct TextStyle {
+    pub font: Font,
+    pub size: f32,
+    pub color: Color,
+    /// Extra space added after each character.
+    pub letter_spacing: f32,
+    pub decoration: TextDecoration,
+    /// Fonts used, in order, for characters which `font` has no glyph for.
+    pub fallback: FontCollection,
+    /// OpenType features applied when shaping, as tags with values. A value of 0 disables a
+    /// feature, 1 enables it, and larger values select an alternate.
+    pub features: Vec<([u8; 4], u32)>,
+}
+
+impl TextStyle {
+    /// Constructs a text style with the given font and size, black color, no letter spacing and
+    /// no decorations.
+    #[inline]
+    pub fn new(font: Font, size: f32) -> TextStyle {
+        TextStyle {
+            font,
+            size,
+            color: Color::rgba(0, 0, 0, 255),
+            letter_spacing: 0.0,
+            decoration: TextDecoration::default(),
+            fallback: FontCollection::new(),
+            features: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn color(&mut self, color: Color) -> &mut Self {
+        self.color = color;
+        self
+    }
+
+    #[inline]
+    pub fn letter_spacing(&mut self, letter_spacing: f32) -> &mut Self {
+        self.letter_spacing = letter_spacing;
+        self
+    }
+
+    #[inline]
+    pub fn decoration(&mut self, decoration: TextDecoration) -> &mut Self {
+        self.decoration = decoration;
+        self
+    }
+
+    #[inline]
+    pub fn fallback(&mut self, fallback: &FontCollection) -> &mut Self {
+        self.fallback = fallback.clone();
+        self
+    }
+
+    /// Sets the value of an OpenType feature, such as `(*b"tnum", 1)` for tabular numbers or
+    /// `(*b"liga", 0)` to disable standard ligatures.
+    #[inline]
+    pub fn feature(&mut self, tag: [u8; 4], value: u32) -> &mut Self {
+        match self.features.iter_mut().find(|(t, _)| *t == tag) {
+            Some(feature) => feature.1 = value,
+            None => self.features.push((tag, value)),
+        }
+        self
+    }
+
+    /// Gets the font with the given index, where 0 is the primary font and the fallback fonts
+    /// follow in order.
+    #[inline]
+    pub(crate) fn font_at(&self, index: usize) -> &Font {
+        match index {
+            0 => &self.font,
+            _ => &self.fallback.fonts[index - 1],
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TextSpan {
+    /// The byte range of the text covered by the span.
+    pub range: Range<usize>,
+    pub style: TextStyle,
+}
+
+/// A string with styles attached to ranges of it.
+///
+/// The spans of an `AttributedText` are sorted and cover the whole string without overlapping.
+#[derive(Clone)]
+pub struct AttributedText {
+    text: String,
+    spans: Vec<TextSpan>,
+}
+
+impl AttributedText {
+    /// Constructs an attributed string with a single style.
+    ///
+    /// The style is also used for the metrics of the empty line if the text is empty.
+    #[inline]
+    pub fn new(text: &str, style: &TextStyle) -> AttributedText {
+        AttributedText {
+            text: text.to_owned(),
+            spans: vec![TextSpan {
+                range: 0..text.len(),
+                style: style.clone(),
+            }],
+        }
+    }
+
+    /// Appends text with the given style.
+    pub fn push(&mut self, text: &str, style: &TextStyle) -> &mut Self {
+        if text.is_empty() {
+            return self;
+        }
+
+        if self.text.is_empty() {
+            self.spans.clear();
+        }
+
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.spans.push(TextSpan {
+            range: start..self.text.len(),
+            style: style.clone(),
+        });
+
+        self
+    }
+
+    /// Applies a style to a byte range of the text, replacing the styles previously applied to
+    /// it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or does not lie on `char` boundaries.
+    pub fn set_style(&mut self, range: Range<usize>, style: &TextStyle) -> &mut Self {
+        assert!(
+            self.text.get(range.clone()).is_some(),
+            "invalid range for attributed text"
+        );
+        if range.is_empty() {
+            return self;
+        }
+
+        let mut spans = Vec::with_capacity(self.spans.len() + 2);
+        for span in &self.spans {
+            if span.range.start < range.start {
+                spans.push(TextSpan {
+                    range: span.range.start..span.range.end.min(range.start),
+                    style: span.style.clone(),
+                });
+            }
+        }
+        spans.push(TextSpan {
+            range: range.clone(),
+            style: style.clone(),
+        });
+        for span in &self.spans {
+            if span.range.end > range.end {
+                spans.push(TextSpan {
+                    range: span.range.start.max(range.end)..span.range.end,
+                    style: span.style.clone(),
+                });
+            }
+        }
+        self.spans = spans;
+
+        self
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[inline]
+    pub fn spans(&self) -> &[TextSpan] {
+        &self.spans
+    }
+}
+
+/// A sequence of glyphs on a line of a [`TextLayout`] which share a style and font.
+#[derive(Clone)]
+pub struct GlyphRun {
+    /// The range of [`TextLayout::glyphs`] in the run.
+    pub glyphs: Range<usize>,
+    /// The index of the style of the run in [`TextLayout::styles`].
+    pub style: usize,
+    /// The font of the glyphs, which is either the font of the style or one of its fallback
+    /// fonts.
+    pub font: Font,
+    /// The horizontal offset of the start of the run.
+    pub x: f32,
+    /// The advance width of the run, excluding trailing whitespace at the end of a line.
+    pub width: f32,
+    /// The vertical offset of the baseline of the run.
+    pub baseline: f32,
+}
+
+/// The glyphs on a line of a [`TextLayout`] which display a range of the source text that cannot
+/// be divided further by shaping, such as a single character or a ligature.
+#[derive(Clone, Debug)]
+pub struct Cluster {
+    /// The byte range of the source text displayed by the cluster.
+    pub text: Range<usize>,
+    /// The range of [`TextLayout::glyphs`] in the cluster.
+    pub glyphs: Range<usize>,
+    /// The horizontal offset of the left edge of the cluster.
+    pub x: f32,
+    pub width: f32,
+    /// Whether the text of the cluster runs from right to left.
+    pub rtl: bool,
+}
+
 #[derive(Clone)]
 pub struct TextLayout {
-    width: f32,
-    height: f32,
-    glyphs: Vec<Glyph>,
+    pub(crate) text: String,
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+    pub(crate) glyphs: Vec<Glyph>,
+    pub(crate) lines: Vec<Line>,
+    pub(crate) runs: Vec<GlyphRun>,
+    pub(crate) clusters: Vec<Cluster>,
+    pub(crate) styles: Vec<TextStyle>,
 }
 
 impl TextLayout {
     #[inline]
     pub fn empty() -> TextLayout {
         TextLayout {
+            text: String::new(),
             width: 0.0,
             height: 0.0,
             glyphs: Vec::new(),
+            lines: Vec::new(),
+            runs: Vec::new(),
+            clusters: Vec::new(),
+            styles: Vec::new(),
         }
     }
 
+    /// Lays out text with the default [`ParagraphStyle`], which breaks lines only at explicit
+    /// line breaks.
     #[inline]
     pub fn new(text: &str, font: &Font, size: f32) -> TextLayout {
-        let mut buf = UnicodeBuffer::new();
-        buf.push_str(text);
-        let glyph_buf = rustybuzz::shape(&font.face, &[], buf);
-
-        let scale = size / font.face.units_per_em() as f32;
-
-        let mut offset = 0.0;
-        let mut glyphs = Vec::with_capacity(glyph_buf.len());
-        for (info, glyph_pos) in zip(glyph_buf.glyph_infos(), glyph_buf.glyph_positions()) {
-            glyphs.push(Glyph {
-                id: info.glyph_id as u16,
-                x: offset + scale * glyph_pos.x_offset as f32,
-                y: scale * glyph_pos.y_offset as f32,
-            });
-
-            offset += scale * glyph_pos.x_advance as f32;
-        }
+        TextLayout::with_style(text, font, size, &ParagraphStyle::new())
+    }
 
-        let height = scale * (font.face.ascender() - font.face.descender()) as f32;
+    /// Lays out text in a single font and size according to `style`.
+    #[inline]
+    pub fn with_style(text: &str, font: &Font, size: f32, style: &ParagraphStyle) -> TextLayout {
+        let text = AttributedText::new(text, &TextStyle::new(font.clone(), size));
+        TextLayout::attributed(&text, style)
+    }
 
-        TextLayout {
-            width: offset,
-            height,
-            glyphs,
-        }
+    /// The source text of the layout.
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
     }
 
     #[inline]
@@ -83,4 +639,203 @@ impl TextLayout {
     pub fn glyphs(&self) -> &[Glyph] {
         &self.glyphs
     }
+
+    #[inline]
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    #[inline]
+    pub fn runs(&self) -> &[GlyphRun] {
+        &self.runs
+    }
+
+    /// The vertical offset of the baseline of the first line, for aligning the layout with other
+    /// text. This is 0 for a layout with no lines.
+    #[inline]
+    pub fn baseline(&self) -> f32 {
+        self.lines.first().map_or(0.0, |line| line.baseline)
+    }
+
+    /// The clusters of each line in visual order, indexed by [`Line::clusters`].
+    #[inline]
+    pub fn clusters(&self) -> &[Cluster] {
+        &self.clusters
+    }
+
+    /// The styles of the spans of the source text, indexed by [`GlyphRun::style`].
+    #[inline]
+    pub fn styles(&self) -> &[TextStyle] {
+        &self.styles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT_PATH: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/examples/res/SourceSansPro-Regular.otf"
+    );
+
+    /// Builds a collection containing each of the given fonts, which must be single fonts with
+    /// tables at 4-byte aligned offsets.
+    fn collection(fonts: &[&[u8]]) -> Vec<u8> {
+        let read_u16 = |data: &[u8], at: usize| u16::from_be_bytes([data[at], data[at + 1]]);
+        let read_u32 =
+            |data: &[u8], at: usize| u32::from_be_bytes(data[at..at + 4].try_into().unwrap());
+
+        let mut data = Vec::new();
+        data.extend_from_slice(b"ttcf");
+        data.extend_from_slice(&0x00010000u32.to_be_bytes());
+        data.extend_from_slice(&(fonts.len() as u32).to_be_bytes());
+        let offsets = data.len();
+        data.resize(offsets + 4 * fonts.len(), 0);
+
+        for (i, font) in fonts.iter().enumerate() {
+            while data.len() % 4 != 0 {
+                data.push(0);
+            }
+            let base = data.len();
+            data[offsets + 4 * i..offsets + 4 * i + 4]
+                .copy_from_slice(&(base as u32).to_be_bytes());
+            data.extend_from_slice(font);
+
+            // Table offsets are relative to the start of the collection.
+            for table in 0..read_u16(font, 4) as usize {
+                let at = 12 + 16 * table + 8;
+                let offset = read_u32(font, at) + base as u32;
+                data[base + at..base + at + 4].copy_from_slice(&offset.to_be_bytes());
+            }
+        }
+
+        data
+    }
+
+    #[test]
+    fn load() {
+        let data = fs::read(FONT_PATH).unwrap();
+
+        let from_vec = Font::from_vec(data.clone(), 0).unwrap();
+        let from_arc = Font::from_arc(data.clone().into(), 0).unwrap();
+        let from_file = Font::from_file(FONT_PATH, 0).unwrap();
+        let mapped = unsafe { Font::from_file_mapped(FONT_PATH, 0) }.unwrap();
+        for font in [&from_vec, &from_arc, &from_file, &mapped] {
+            assert_eq!(font.data(), &data[..]);
+            assert!(font.has_glyph('A'));
+        }
+
+        // Fonts outlive the data they were loaded from.
+        let glyphs = TextLayout::new("Text", &from_vec, 12.0).glyphs().len();
+        let clone = from_vec.clone();
+        drop(from_vec);
+        assert_eq!(TextLayout::new("Text", &clone, 12.0).glyphs().len(), glyphs);
+
+        assert!(Font::from_vec(data, 1).is_none());
+        assert!(Font::from_vec(vec![0; 16], 0).is_none());
+        assert!(matches!(
+            Font::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"), 0),
+            Err(FontError::InvalidFont)
+        ));
+        assert!(matches!(
+            Font::from_file("/nonexistent/font.ttf", 0),
+            Err(FontError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn collection_faces() {
+        let regular = include_bytes!("../examples/res/SourceSansPro-Regular.otf");
+        let tiny = include_bytes!("../examples/res/SourceSansPro-Regular-Tiny.ttf");
+        assert_eq!(Font::face_count(regular), 1);
+        assert_eq!(Font::face_count(b"not a font"), 0);
+
+        let data: Arc<[u8]> = collection(&[regular, tiny]).into();
+        assert_eq!(Font::face_count(&data), 2);
+
+        let fonts: Vec<_> = (0..Font::face_count(&data))
+            .map(|index| Font::from_arc(data.clone(), index).unwrap())
+            .collect();
+        assert_eq!(fonts[1].index(), 1);
+        assert!(fonts[0].has_glyph(','));
+        assert!(!fonts[1].has_glyph(','));
+        assert!(Font::from_arc(data, 2).is_none());
+    }
+
+    #[test]
+    fn attributed_text_spans() {
+        let font = Font::from_bytes(
+            include_bytes!("../examples/res/SourceSansPro-Regular.otf"),
+            0,
+        )
+        .unwrap();
+        let style = TextStyle::new(font.clone(), 12.0);
+        let large = TextStyle::new(font, 20.0);
+
+        let mut text = AttributedText::new("", &style);
+        text.push("hello ", &style).push("world", &large);
+        assert_eq!(text.text(), "hello world");
+        let ranges: Vec<_> = text.spans().iter().map(|span| span.range.clone()).collect();
+        assert_eq!(ranges, [0..6, 6..11]);
+
+        text.set_style(3..8, &large);
+        let spans: Vec<_> =
+            text.spans().iter().map(|span| (span.range.clone(), span.style.size)).collect();
+        assert_eq!(spans, [(0..3, 12.0), (3..8, 20.0), (8..11, 20.0)]);
+    }
+
+    #[test]
+    fn variations() {
+        use crate::glyph_cache::GlyphCache;
+
+        let font = Font::from_bytes(include_bytes!("../examples/res/TestGVAROne.ttf"), 0).unwrap();
+        let light = font.with_variations(&[(*b"wght", 100.0)]);
+        let heavy = font.with_variations(&[(*b"wght", 900.0), (*b"none", 1.0)]);
+        assert_ne!(light.id, font.id);
+        assert_ne!(light.id, heavy.id);
+
+        // Outlines vary with the weight.
+        let glyph = font.face.glyph_index('\u{5F4C}').unwrap().0;
+        let mut cache = GlyphCache::new();
+        let mut bounds = |font: &Font| cache.outline(font, glyph).unwrap().control_bounds();
+        let (default, light, heavy) = (bounds(&font), bounds(&light), bounds(&heavy));
+        assert_ne!(default, light);
+        assert_ne!(default, heavy);
+        assert_ne!(light, heavy);
+
+        // Glyph metrics vary too, and the original font is unaffected.
+        let light = font.with_variations(&[(*b"wght", 100.0)]);
+        assert_ne!(
+            font.glyph_bounds(glyph, 16.0),
+            light.glyph_bounds(glyph, 16.0)
+        );
+        assert_eq!(bounds(&font), default);
+    }
+
+    #[test]
+    fn metrics() {
+        let font = Font::from_file(FONT_PATH, 0).unwrap();
+        assert_eq!(font.units_per_em(), 1000);
+
+        let metrics = font.metrics(20.0);
+        assert!((metrics.ascent - 19.68).abs() < 1e-3);
+        assert!((metrics.descent - 5.46).abs() < 1e-3);
+        assert!((metrics.cap_height - 13.2).abs() < 1e-3);
+        assert!((metrics.x_height - 9.72).abs() < 1e-3);
+        assert!(metrics.underline_position < 0.0);
+        assert!(metrics.strikeout_position > 0.0 && metrics.strikeout_position < metrics.x_height);
+
+        // Bounds are relative to the origin on the baseline, with y pointing down.
+        let glyph = font.glyph_index('H').unwrap();
+        let bounds = font.glyph_bounds(glyph, 20.0).unwrap();
+        assert!((bounds.min.y + metrics.cap_height).abs() < 0.2);
+        assert_eq!(bounds.max.y, 0.0);
+        assert!(font.glyph_bounds(font.glyph_index(' ').unwrap(), 20.0).is_none());
+
+        let layout = TextLayout::new("Hx", &font, 20.0);
+        assert_eq!(layout.glyphs()[1].x, font.glyph_advance(glyph, 20.0));
+        assert_eq!(layout.baseline(), metrics.ascent);
+        assert_eq!(TextLayout::empty().baseline(), 0.0);
+    }
 }
@@ -1,11 +1,13 @@
 use std::error::Error;
 use std::path;
 
-use reflector_graphics::{Affine, Canvas, Color, Path, Point};
+use reflector_graphics::{
+    Affine, Canvas, Color, FillRule, LineCap, LineJoin, Path, Point, StrokeStyle,
+};
 
 pub enum Style {
-    Fill,
-    Stroke(f32),
+    Fill(FillRule),
+    Stroke(StrokeStyle),
 }
 
 pub struct Command {
@@ -25,67 +27,82 @@ pub fn from_file<P: AsRef<path::Path>>(path: P) -> Result<Vec<Command>, Box<dyn
 
 fn build_list(node: &usvg::Node, commands: &mut Vec<Command>) {
     use usvg::NodeExt;
-    match *node.borrow() {
-        usvg::NodeKind::Path(ref p) => {
-            let t = node.transform();
-            let transform = Affine::new([
-                t.a as f32, t.c as f32, t.e as f32, t.b as f32, t.d as f32, t.f as f32,
-            ]);
+    if let usvg::NodeKind::Path(ref p) = *node.borrow() {
+        let t = node.transform();
+        let transform = Affine::new([
+            t.a as f32, t.c as f32, t.e as f32, t.b as f32, t.d as f32, t.f as f32,
+        ]);
 
-            let mut path = Path::new();
-            for segment in p.data.0.iter() {
-                match *segment {
-                    usvg::PathSegment::MoveTo { x, y } => {
-                        path.move_to(transform * Point::new(x as f32, y as f32));
-                    }
-                    usvg::PathSegment::LineTo { x, y } => {
-                        path.line_to(transform * Point::new(x as f32, y as f32));
-                    }
-                    usvg::PathSegment::CurveTo {
-                        x1,
-                        y1,
-                        x2,
-                        y2,
-                        x,
-                        y,
-                    } => {
-                        path.cubic_to(
-                            transform * Point::new(x1 as f32, y1 as f32),
-                            transform * Point::new(x2 as f32, y2 as f32),
-                            transform * Point::new(x as f32, y as f32),
-                        );
-                    }
-                    usvg::PathSegment::ClosePath => {
-                        path.close();
-                    }
+        let mut path = Path::new();
+        for segment in p.data.0.iter() {
+            match *segment {
+                usvg::PathSegment::MoveTo { x, y } => {
+                    path.move_to(transform * Point::new(x as f32, y as f32));
+                }
+                usvg::PathSegment::LineTo { x, y } => {
+                    path.line_to(transform * Point::new(x as f32, y as f32));
+                }
+                usvg::PathSegment::CurveTo {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y,
+                } => {
+                    path.cubic_to(
+                        transform * Point::new(x1 as f32, y1 as f32),
+                        transform * Point::new(x2 as f32, y2 as f32),
+                        transform * Point::new(x as f32, y as f32),
+                    );
+                }
+                usvg::PathSegment::ClosePath => {
+                    path.close();
                 }
             }
+        }
 
-            if let Some(ref fill) = p.fill {
-                if let usvg::Paint::Color(color) = fill.paint {
-                    let color =
-                        Color::rgba(color.red, color.green, color.blue, fill.opacity.to_u8());
-                    commands.push(Command {
-                        path: path.clone(),
-                        style: Style::Fill,
-                        color,
-                    });
-                }
+        if let Some(ref fill) = p.fill {
+            if let usvg::Paint::Color(color) = fill.paint {
+                let color = Color::rgba(color.red, color.green, color.blue, fill.opacity.to_u8());
+                let fill_rule = match fill.rule {
+                    usvg::FillRule::NonZero => FillRule::NonZero,
+                    usvg::FillRule::EvenOdd => FillRule::EvenOdd,
+                };
+                commands.push(Command {
+                    path: path.clone(),
+                    style: Style::Fill(fill_rule),
+                    color,
+                });
             }
+        }
 
-            if let Some(ref stroke) = p.stroke {
-                if let usvg::Paint::Color(color) = stroke.paint {
-                    let color =
-                        Color::rgba(color.red, color.green, color.blue, stroke.opacity.to_u8());
-                    commands.push(Command {
-                        path,
-                        style: Style::Stroke(stroke.width.value() as f32),
-                        color,
-                    });
+        if let Some(ref stroke) = p.stroke {
+            if let usvg::Paint::Color(color) = stroke.paint {
+                let color = Color::rgba(color.red, color.green, color.blue, stroke.opacity.to_u8());
+                let mut style = StrokeStyle::new(stroke.width.value() as f32);
+                style.join(match stroke.linejoin {
+                    usvg::LineJoin::Miter => LineJoin::Miter,
+                    usvg::LineJoin::Round => LineJoin::Round,
+                    usvg::LineJoin::Bevel => LineJoin::Bevel,
+                });
+                style.cap(match stroke.linecap {
+                    usvg::LineCap::Butt => LineCap::Butt,
+                    usvg::LineCap::Round => LineCap::Round,
+                    usvg::LineCap::Square => LineCap::Square,
+                });
+                style.miter_limit(stroke.miterlimit.value() as f32);
+                if let Some(ref dasharray) = stroke.dasharray {
+                    let dashes: Vec<f32> = dasharray.iter().map(|&dash| dash as f32).collect();
+                    style.dashes(&dashes, stroke.dashoffset);
                 }
+                commands.push(Command {
+                    path,
+                    style: Style::Stroke(style),
+                    color,
+                });
             }
         }
-        _ => {}
     }
 
     for child in node.children() {
@@ -96,11 +113,11 @@ fn build_list(node: &usvg::Node, commands: &mut Vec<Command>) {
 pub fn render(commands: &[Command], transform: Affine, canvas: &mut Canvas) {
     for command in commands {
         match command.style {
-            Style::Fill => {
-                canvas.fill_path(&command.path, transform, command.color);
+            Style::Fill(fill_rule) => {
+                canvas.fill_path(&command.path, fill_rule, transform, command.color);
             }
-            Style::Stroke(width) => {
-                canvas.stroke_path(&command.path, width, transform, command.color);
+            Style::Stroke(ref style) => {
+                canvas.stroke_path(&command.path, style, transform, command.color);
             }
         }
     }
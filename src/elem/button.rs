@@ -1,4 +1,4 @@
-use graphics::{Affine, Canvas, Color, Path};
+use graphics::{Affine, Canvas, Color, FillRule, Path};
 
 use super::{Context, Elem, Event, Response};
 use crate::{AsAny, Point, ProposedSize, Size};
@@ -51,17 +51,22 @@ impl Elem for Button {
     }
 
     fn render(&mut self, cx: &mut Context, canvas: &mut Canvas) {
-        let mut rect = Path::new();
-        rect.move_to(Point::new(0.0, 0.0));
-        rect.line_to(Point::new(0.0, self.size.height));
-        rect.line_to(Point::new(self.size.width, self.size.height));
-        rect.line_to(Point::new(self.size.width, 0.0));
-        rect.close();
+        let rect = Path::rect(Point::new(0.0, 0.0), self.size.width, self.size.height);
 
         if self.hover {
-            canvas.fill_path(&rect, Affine::id(), Color::rgba(220, 220, 220, 255));
+            canvas.fill_path(
+                &rect,
+                FillRule::NonZero,
+                Affine::id(),
+                Color::rgba(220, 220, 220, 255),
+            );
         } else {
-            canvas.fill_path(&rect, Affine::id(), Color::rgba(180, 180, 180, 255));
+            canvas.fill_path(
+                &rect,
+                FillRule::NonZero,
+                Affine::id(),
+                Color::rgba(180, 180, 180, 255),
+            );
         }
 
         self.label.render(cx, canvas);